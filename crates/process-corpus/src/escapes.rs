@@ -0,0 +1,104 @@
+use std::{borrow::Cow, io};
+
+/// Decode `\xNN` byte escapes into UTF-8 text.
+///
+/// Consecutive escapes are decoded together, so `\xc3\xa9` becomes `é`. The
+/// escapes of bytes that are not valid UTF-8 are left as they are, with a
+/// warning, and the rest of their run is decoded.
+pub fn decode_escapes(s: &str) -> Cow<'_, str> {
+    if !s.contains(r"\x") {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(ix) = rest.find(r"\x") {
+        out.push_str(&rest[..ix]);
+        rest = &rest[ix..];
+
+        let mut bytes = Vec::new();
+        let mut run_len = 0;
+        while let Some(b) = parse_escape(&rest[run_len..]) {
+            bytes.push(b);
+            run_len += 4;
+        }
+        if run_len == 0 {
+            // a lone `\x` that is not followed by two hex digits
+            out.push_str(&rest[..2]);
+            rest = &rest[2..];
+            continue;
+        }
+        // each byte has an escape of 4 characters in `rest`
+        let mut num_bytes = 0;
+        for chunk in bytes.utf8_chunks() {
+            out.push_str(chunk.valid());
+            num_bytes += chunk.valid().len();
+            if !chunk.invalid().is_empty() {
+                let bad = &rest[4 * num_bytes..4 * (num_bytes + chunk.invalid().len())];
+                log::warn!("Invalid UTF-8 in escapes {}, keeping them", bad);
+                out.push_str(bad);
+                num_bytes += chunk.invalid().len();
+            }
+        }
+        rest = &rest[run_len..];
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+fn parse_escape(s: &str) -> Option<u8> {
+    let hex = s.strip_prefix(r"\x")?.get(..2)?;
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u8::from_str_radix(hex, 16).ok()
+}
+
+/// Escape every non-ASCII byte as `\xNN`, the inverse of [`decode_escapes`].
+pub fn encode_escapes(s: &str) -> Cow<'_, str> {
+    if s.is_ascii() {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len() * 2);
+    for c in s.chars() {
+        if c.is_ascii() {
+            out.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!(r"\x{:02x}", b));
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// A writer that escapes every non-ASCII byte as `\xNN`, like [`encode_escapes`].
+#[derive(Debug)]
+pub struct EscapeWriter<W> {
+    inner: W,
+}
+
+impl<W: io::Write> EscapeWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+}
+
+impl<W: io::Write> io::Write for EscapeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for chunk in buf.split_inclusive(|b| !b.is_ascii()) {
+            match chunk.split_last() {
+                Some((last, ascii)) if !last.is_ascii() => {
+                    self.inner.write_all(ascii)?;
+                    write!(self.inner, r"\x{:02x}", last)?;
+                }
+                _ => self.inner.write_all(chunk)?,
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -1,29 +1,114 @@
 use std::fmt;
 
-#[derive(Debug, Clone, Default)]
+use crate::{ParseError, ParseErrorKind};
+
+/// One token of the tab format, keeping every column so it can be written back unchanged.
+///
+/// The columns are position, word form, prefix/suffix marker, lemgrams, lemmas
+/// and senses, followed by any extra columns.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct LemmaToken {
-    possible_senses: Vec<String>,
+    position: String,
+    word: String,
+    affix_marker: String,
+    possible_lemgrams: Vec<String>,
     possible_lemmas: Vec<String>,
-    is_prefix: bool,
-    is_suffix: bool,
-    // possible_lemgrams: Vec<String>,
+    possible_senses: Vec<String>,
+    extra_columns: Vec<String>,
 }
 
 impl LemmaToken {
+    /// Create a token that did not come from the tab format.
+    ///
+    /// It is written to the tab format without prefix/suffix marker and extra columns.
+    pub fn new(
+        position: impl Into<String>,
+        word: impl Into<String>,
+        possible_lemgrams: Vec<String>,
+        possible_lemmas: Vec<String>,
+        possible_senses: Vec<String>,
+    ) -> Self {
+        Self {
+            position: position.into(),
+            word: word.into(),
+            affix_marker: "_".to_string(),
+            possible_lemgrams,
+            possible_lemmas,
+            possible_senses,
+            extra_columns: Vec::new(),
+        }
+    }
+
+    /// Number of columns every line must have.
+    pub const NUM_COLUMNS: usize = 6;
+
+    /// Parse a line, repairing it if it is malformed.
+    ///
+    /// Missing columns are treated as `_` and empty values in multi-valued
+    /// columns are kept, see [`without_empty_values`](Self::without_empty_values).
+    /// Use [`try_parse_line`](Self::try_parse_line) to detect malformed lines.
     pub fn parse_line(line: &str) -> Self {
         let xs: Vec<&str> = line.split('\t').collect();
-        let mut out = Self::default();
-        if xs[4] != "_" {
-            for s in xs[4].split("|") {
-                out.possible_lemmas.push(s.to_string());
-            }
+        let col = |i: usize| xs.get(i).copied().unwrap_or("_");
+        Self {
+            position: col(0).to_string(),
+            word: col(1).to_string(),
+            affix_marker: col(2).to_string(),
+            possible_lemgrams: parse_multi_valued(col(3)),
+            possible_lemmas: parse_multi_valued(col(4)),
+            possible_senses: parse_multi_valued(col(5)),
+            extra_columns: xs.iter().skip(6).map(|x| x.to_string()).collect(),
+        }
+    }
+
+    /// Parse a line, failing if it has too few columns.
+    ///
+    /// Empty values are accepted, as [`Display`](std::fmt::Display) writes
+    /// them back.
+    ///
+    /// The line number of the returned error is 0.
+    pub fn try_parse_line(line: &str) -> Result<Self, ParseError> {
+        let xs: Vec<&str> = line.split('\t').collect();
+        if xs.len() < Self::NUM_COLUMNS {
+            return Err(ParseError::new(
+                0,
+                xs.len() + 1,
+                line,
+                ParseErrorKind::MissingColumns {
+                    expected: Self::NUM_COLUMNS,
+                    found: xs.len(),
+                },
+            ));
         }
-        if xs[5] != "_" {
-            for s in xs[5].split("|") {
-                out.possible_senses.push(s.to_string());
-            }
+        Ok(Self::parse_line(line))
+    }
+
+    /// The token with the empty values of the multi-valued columns dropped.
+    pub fn without_empty_values(mut self) -> Self {
+        for values in [
+            &mut self.possible_lemgrams,
+            &mut self.possible_lemmas,
+            &mut self.possible_senses,
+        ] {
+            values.retain(|v| !v.is_empty());
         }
-        out
+        self
+    }
+
+    pub fn position(&self) -> &str {
+        &self.position
+    }
+    pub fn word(&self) -> &str {
+        &self.word
+    }
+    pub fn is_prefix(&self) -> bool {
+        self.affix_marker == "(pfx)"
+    }
+    pub fn is_suffix(&self) -> bool {
+        self.affix_marker == "(sfx)"
+    }
+    pub fn possible_lemgrams(&self) -> &[String] {
+        &self.possible_lemgrams
     }
     pub fn possible_senses(&self) -> &[String] {
         &self.possible_senses
@@ -31,48 +116,41 @@ impl LemmaToken {
     pub fn possible_lemmas(&self) -> &[String] {
         &self.possible_lemmas
     }
+    /// The columns after the senses column, unchanged.
+    pub fn extra_columns(&self) -> &[String] {
+        &self.extra_columns
+    }
 }
 
-impl fmt::Display for LemmaToken {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // if self.nodes.len() == 0 {
-        f.write_str("_\t")?;
-        // } else {
-        // for n in &self.nodes {
-        // todo!("n.position");
-        // }
-        // }
-        // // if self.nodes.len() == 0 {
-        f.write_str("_\t")?;
-        // } else {
-        // for n in &self.nodes {
-        // todo!("n.word");
-        // }
-        // }
-        if self.is_prefix {
-            f.write_str("(pfx)\t")?;
-        } else if self.is_suffix {
-            f.write_str("(sfx)\t")?;
-        } else {
-            f.write_str("_\t")?;
-        }
-
-        // if self.possible_lemgrams.len() > 0 {
-        //     f.write_fmt(format_args!("{}\t",self.possible_lemgrams.join("|")))?;
-        // } else {
-        f.write_str("_\t")?;
-        // }
+fn parse_multi_valued(cell: &str) -> Vec<String> {
+    if cell == "_" {
+        return Vec::new();
+    }
+    cell.split('|').map(|s| s.to_string()).collect()
+}
 
-        if !self.possible_lemmas.is_empty() {
-            f.write_fmt(format_args!("{}\t", self.possible_lemmas.join("|")))?;
-        } else {
-            f.write_str("_\t")?;
-        }
+fn write_multi_valued(f: &mut fmt::Formatter<'_>, values: &[String]) -> fmt::Result {
+    if values.is_empty() {
+        f.write_str("_")
+    } else {
+        f.write_str(&values.join("|"))
+    }
+}
 
-        if !self.possible_senses.is_empty() {
-            f.write_fmt(format_args!("{}", self.possible_senses.join("|")))?;
-        } else {
-            f.write_str("_")?;
+impl fmt::Display for LemmaToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t",
+            self.position, self.word, self.affix_marker
+        )?;
+        write_multi_valued(f, &self.possible_lemgrams)?;
+        f.write_str("\t")?;
+        write_multi_valued(f, &self.possible_lemmas)?;
+        f.write_str("\t")?;
+        write_multi_valued(f, &self.possible_senses)?;
+        for extra in &self.extra_columns {
+            write!(f, "\t{}", extra)?;
         }
         Ok(())
     }
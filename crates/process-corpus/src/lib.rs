@@ -1,5 +1,9 @@
+mod escapes;
 mod lemma_token;
+mod parse_error;
 mod process_corpus;
 
+pub use self::escapes::{EscapeWriter, decode_escapes, encode_escapes};
 pub use self::lemma_token::LemmaToken;
-pub use process_corpus::read_lemma_tokens;
+pub use self::parse_error::{CorpusError, ParseError, ParseErrorKind};
+pub use process_corpus::{LemmaTokenReader, ParseMode, read_lemma_tokens};
@@ -0,0 +1,85 @@
+use std::{fmt, io};
+
+use miette::SourceSpan;
+
+/// A malformed line in the input, with its position and raw text.
+#[derive(Debug, Clone, thiserror::Error, miette::Diagnostic)]
+#[error("Malformed input on line {line_number}, column {column}: {kind}")]
+pub struct ParseError {
+    /// 1-based line number in the input.
+    pub line_number: usize,
+    /// 1-based number of the offending column.
+    pub column: usize,
+    pub kind: ParseErrorKind,
+    #[source_code]
+    pub line: String,
+    #[label("{kind}")]
+    span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    MissingColumns { expected: usize, found: usize },
+    Other(String),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingColumns { expected, found } => {
+                write!(f, "expected at least {} columns, found {}", expected, found)
+            }
+            Self::Other(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl ParseError {
+    /// Create an error for `column` (1-based) of `line`, pointing at that column's text.
+    pub fn new(line_number: usize, column: usize, line: &str, kind: ParseErrorKind) -> Self {
+        let span = column_span(line, column);
+        Self {
+            line_number,
+            column,
+            kind,
+            line: line.to_string(),
+            span,
+        }
+    }
+
+    pub fn with_line_number(mut self, line_number: usize) -> Self {
+        self.line_number = line_number;
+        self
+    }
+
+    /// Point the error at `line` as it was read, before decoding or repairs,
+    /// at `line_number` in the input.
+    pub fn with_source_line(mut self, line_number: usize, line: &str) -> Self {
+        self.line_number = line_number;
+        self.span = column_span(line, self.column);
+        self.line = line.to_string();
+        self
+    }
+}
+
+/// The byte span of tab-separated `column` (1-based), or all of `line` if it is missing.
+fn column_span(line: &str, column: usize) -> SourceSpan {
+    let mut start = 0;
+    for (i, cell) in line.split('\t').enumerate() {
+        if i + 1 == column {
+            return (start, cell.len()).into();
+        }
+        start += cell.len() + 1;
+    }
+    (0, line.len()).into()
+}
+
+/// An error reading a corpus: either I/O or a malformed line.
+#[derive(Debug, thiserror::Error, miette::Diagnostic)]
+pub enum CorpusError {
+    #[error("I/O error")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(#[from] ParseError),
+}
@@ -1,33 +1,113 @@
-use std::io;
-
-use crate::LemmaToken;
-
-pub fn read_lemma_tokens(reader: &mut dyn io::BufRead) -> io::Result<Option<Vec<LemmaToken>>> {
-    let mut out = Vec::new();
-    let mut line = String::new();
-    loop {
-        line.clear();
-        let num_read = reader.read_line(&mut line)?;
-        log::trace!("Line read: {}", line);
-        if num_read == 0 {
-            if out.is_empty() {
-                return Ok(None);
-            } else {
+use std::{borrow::Cow, io};
+
+use crate::{CorpusError, LemmaToken, ParseError, decode_escapes};
+
+/// How malformed lines are handled when reading the tab format.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Fail on the first malformed line.
+    #[default]
+    Strict,
+    /// Repair malformed lines if possible, otherwise skip them, with a warning.
+    Lenient,
+}
+
+impl std::str::FromStr for ParseMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Self::Strict),
+            "lenient" => Ok(Self::Lenient),
+            _ => Err(format!("unknown parse mode '{}'", s)),
+        }
+    }
+}
+
+/// Reads sentences of [`LemmaToken`]s, keeping track of the line number.
+#[derive(Debug, Default)]
+pub struct LemmaTokenReader {
+    mode: ParseMode,
+    line_number: usize,
+    num_malformed: usize,
+}
+
+impl LemmaTokenReader {
+    pub fn new(mode: ParseMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    /// The number of lines read so far.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// The number of malformed lines repaired or skipped so far in lenient mode.
+    pub fn num_malformed(&self) -> usize {
+        self.num_malformed
+    }
+
+    /// Read the next sentence, or `None` at the end of the input.
+    pub fn read_sentence(
+        &mut self,
+        reader: &mut dyn io::BufRead,
+    ) -> Result<Option<Vec<LemmaToken>>, CorpusError> {
+        let mut out = Vec::new();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let num_read = reader.read_line(&mut line)?;
+            log::trace!("Line read: {}", line);
+            if num_read == 0 {
+                if out.is_empty() {
+                    return Ok(None);
+                } else {
+                    return Ok(Some(out));
+                }
+            }
+            self.line_number += 1;
+            if line.trim().is_empty() {
                 return Ok(Some(out));
             }
+            // empty leading and trailing columns are kept
+            let raw_line = line.trim_end_matches(['\n', '\r']);
+            match LemmaToken::try_parse_line(&clean_input(raw_line)) {
+                Ok(lt) => out.push(lt),
+                Err(err) => {
+                    let err = err.with_source_line(self.line_number, raw_line);
+                    if self.mode == ParseMode::Strict {
+                        return Err(err.into());
+                    }
+                    self.num_malformed += 1;
+                    out.extend(self.repair(err));
+                }
+            }
         }
-        let line_trimmed = line.trim();
-        if line_trimmed.is_empty() {
-            return Ok(Some(out));
+    }
+
+    fn repair(&self, err: ParseError) -> Option<LemmaToken> {
+        if !err.line.contains('\t') {
+            log::warn!("{}, skipping line", err);
+            return None;
         }
-        let clean_line = clean_input(line_trimmed);
-        out.push(LemmaToken::parse_line(&clean_line));
+        log::warn!("{}, repairing line", err);
+        Some(LemmaToken::parse_line(&clean_input(&err.line)).without_empty_values())
     }
 }
 
-fn clean_input(s: &str) -> String {
-    let out = s.replace(r"\xc3\xa5", "å");
-    let out = out.replace(r"\xc3\xa4", "ä");
+/// Read the next sentence in strict mode.
+///
+/// Line numbers in errors are relative to the current position of `reader`,
+/// use [`LemmaTokenReader`] to track them across sentences.
+pub fn read_lemma_tokens(
+    reader: &mut dyn io::BufRead,
+) -> Result<Option<Vec<LemmaToken>>, CorpusError> {
+    LemmaTokenReader::default().read_sentence(reader)
+}
 
-    out.replace(r"\xc3\xb6", "ö")
+fn clean_input(s: &str) -> Cow<'_, str> {
+    decode_escapes(s)
 }
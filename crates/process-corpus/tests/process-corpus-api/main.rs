@@ -1 +1,3 @@
+mod test_escapes;
 mod test_lemma_token;
+mod test_lemma_token_reader;
@@ -0,0 +1,30 @@
+use process_corpus::{decode_escapes, encode_escapes};
+use rstest::rstest;
+
+#[rstest]
+#[case::lowercase_swedish(r"bet\xc3\xa4nkande..1", "betänkande..1")]
+#[case::all_swedish(r"\xc3\xa5\xc3\xa4\xc3\xb6", "åäö")]
+#[case::uppercase_swedish(r"\xc3\x85\xc3\x84\xc3\x96", "ÅÄÖ")]
+#[case::e_acute(r"id\xc3\xa9..1", "idé..1")]
+#[case::u_umlaut(r"m\xc3\xbcsli..nn", "müsli..nn")]
+#[case::uppercase_hex(r"id\xC3\xA9", "idé")]
+#[case::three_bytes(r"\xe2\x82\xac", "€")]
+#[case::no_escapes("riksdag..nn", "riksdag..nn")]
+#[case::invalid_utf8_is_kept(r"a\xc3b", r"a\xc3b")]
+#[case::valid_before_invalid(r"\xc3\xa4\xc3b", r"ä\xc3b")]
+#[case::valid_after_invalid(r"\xFF\xc3\xa4", r"\xFFä")]
+#[case::invalid_between_valid(r"\xc3\xa5\xa4\xc3\xb6", r"å\xa4ö")]
+#[case::lone_backslash_x(r"a\xzz", r"a\xzz")]
+#[case::trailing_backslash_x(r"a\x", r"a\x")]
+fn test_decode_escapes(#[case] input: &str, #[case] expected: &str) {
+    assert_eq!(decode_escapes(input), expected);
+}
+
+#[rstest]
+#[case::swedish("Åtagande..nn", r"\xc3\x85tagande..nn")]
+#[case::e_acute("idé..1", r"id\xc3\xa9..1")]
+#[case::ascii("och..1", "och..1")]
+fn test_encode_escapes(#[case] input: &str, #[case] expected: &str) {
+    assert_eq!(encode_escapes(input), expected);
+    assert_eq!(decode_escapes(expected), input);
+}
@@ -16,3 +16,46 @@ fn test_lemma_tokens() -> eyre::Result<()> {
     insta::assert_debug_snapshot!(actual);
     Ok(())
 }
+
+#[rstest::rstest]
+#[case::example("assets/testing/example1.in.txt")]
+fn test_lemma_token_round_trip(#[case] path: &str) -> eyre::Result<()> {
+    let reader = BufReader::new(File::open(path)?);
+    for line in reader.lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        assert_eq!(LemmaToken::parse_line(&line).to_string(), line);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_lemma_token_keeps_extra_columns() {
+    let line = "7\tutskottet\t(sfx)\tutskott..nn.1\tutskott..nn\tutskott..1|utskott..2\tNN\textra";
+
+    let actual = LemmaToken::parse_line(line);
+
+    assert_eq!(actual.position(), "7");
+    assert_eq!(actual.word(), "utskottet");
+    assert!(actual.is_suffix());
+    assert!(!actual.is_prefix());
+    assert_eq!(actual.possible_lemgrams(), ["utskott..nn.1"]);
+    assert_eq!(actual.extra_columns(), ["NN", "extra"]);
+    assert_eq!(actual.to_string(), line);
+}
+
+#[test]
+fn test_lemma_token_keeps_empty_values() {
+    let line = "1\tx\t_\t|x..nn.1\tx..nn\tx..1||x..2";
+
+    let actual = LemmaToken::parse_line(line);
+
+    assert_eq!(actual.possible_lemgrams(), ["", "x..nn.1"]);
+    assert_eq!(actual.possible_senses(), ["x..1", "", "x..2"]);
+    assert_eq!(actual.to_string(), line);
+    let filtered = actual.without_empty_values();
+    assert_eq!(filtered.possible_lemgrams(), ["x..nn.1"]);
+    assert_eq!(filtered.possible_senses(), ["x..1", "x..2"]);
+}
@@ -0,0 +1,100 @@
+use std::io::Cursor;
+
+use process_corpus::{CorpusError, LemmaTokenReader, ParseErrorKind, ParseMode};
+
+const MALFORMED: &str = "1\ta\t_\t_\tbank..nn\tbank..1|bank..2
+2\tb\t_\t_
+
+1\tc\t_\t_\tx..nn\tx..1||x..2
+garbage
+2\td\t_\t_\td..nn\t_
+";
+
+#[test]
+fn test_strict_reports_line_and_column() {
+    let mut reader = LemmaTokenReader::new(ParseMode::Strict);
+    let mut input = Cursor::new(MALFORMED);
+
+    let Err(CorpusError::Parse(err)) = reader.read_sentence(&mut input) else {
+        panic!("expected a parse error");
+    };
+
+    assert_eq!(err.line_number, 2);
+    assert_eq!(err.column, 5);
+    assert_eq!(err.line, "2\tb\t_\t_");
+    assert_eq!(
+        err.kind,
+        ParseErrorKind::MissingColumns {
+            expected: 6,
+            found: 4
+        }
+    );
+}
+
+#[test]
+fn test_lenient_repairs_and_skips() -> eyre::Result<()> {
+    let mut reader = LemmaTokenReader::new(ParseMode::Lenient);
+    let mut input = Cursor::new(MALFORMED);
+
+    let first = reader.read_sentence(&mut input)?.expect("a sentence");
+    let second = reader.read_sentence(&mut input)?.expect("a sentence");
+
+    assert!(reader.read_sentence(&mut input)?.is_none());
+    let actual: Vec<Vec<String>> = [first, second]
+        .iter()
+        .map(|sen| sen.iter().map(ToString::to_string).collect())
+        .collect();
+    insta::assert_debug_snapshot!(actual);
+    assert_eq!(reader.num_malformed(), 2);
+    assert_eq!(reader.line_number(), 6);
+    Ok(())
+}
+
+#[test]
+fn test_keeps_empty_columns() -> eyre::Result<()> {
+    let mut reader = LemmaTokenReader::new(ParseMode::Strict);
+    let mut input = Cursor::new("1\tx\t_\t_\tx..nn\tx..1\t\r\n2\ty\t_\t_\ty..nn\t_\tNN\n");
+
+    let sentence = reader.read_sentence(&mut input)?.expect("a sentence");
+
+    assert_eq!(sentence[0].extra_columns(), [""]);
+    assert_eq!(sentence[0].to_string(), "1\tx\t_\t_\tx..nn\tx..1\t");
+    assert_eq!(sentence[1].extra_columns(), ["NN"]);
+    Ok(())
+}
+
+#[test]
+fn test_error_has_the_line_as_read() {
+    let mut reader = LemmaTokenReader::new(ParseMode::Strict);
+    let mut input = Cursor::new("1\tx\t_\t_\tx..nn\tx..1\n2\tcaf\\xc3\\xa9\t_\t_\tcafé..nn \r\n");
+
+    let Err(CorpusError::Parse(err)) = reader.read_sentence(&mut input) else {
+        panic!("expected a parse error");
+    };
+
+    assert_eq!(err.line_number, 2);
+    assert_eq!(err.column, 6);
+    assert_eq!(err.line, "2\tcaf\\xc3\\xa9\t_\t_\tcafé..nn ");
+    assert_eq!(
+        err.kind,
+        ParseErrorKind::MissingColumns {
+            expected: 6,
+            found: 5
+        }
+    );
+}
+
+#[test]
+fn test_strict_reads_empty_values_as_written() -> eyre::Result<()> {
+    let line = "1\tx\t_\t|x..nn\tx..nn|\tx..1||x..2";
+    let mut reader = LemmaTokenReader::new(ParseMode::Strict);
+
+    let sentence = reader
+        .read_sentence(&mut Cursor::new(format!("{}\n", line)))?
+        .expect("a sentence");
+
+    assert_eq!(sentence[0].possible_senses(), ["x..1", "", "x..2"]);
+    assert_eq!(sentence[0].to_string(), line);
+    assert_eq!(reader.num_malformed(), 0);
+    Ok(())
+}
@@ -3,4 +3,6 @@ mod saldo_lemgram;
 mod saldo_lexicon;
 mod shared;
 
-pub use saldo_lexicon::SaldoLexicon;
+pub use saldo_entry::{SaldoEntry, SaldoId};
+pub use saldo_lemgram::{SaldoLemgram, SaldoLemgramId};
+pub use saldo_lexicon::{PRIM, SaldoLexicon};
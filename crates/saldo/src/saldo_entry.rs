@@ -17,6 +17,12 @@ impl Borrow<String> for SaldoId {
     }
 }
 
+impl Borrow<str> for SaldoId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
 impl SaldoId {
     pub fn new(s: impl Into<String>) -> Self {
         Self(s.into())
@@ -57,6 +63,25 @@ impl SaldoEntry {
     pub fn get_id(&self) -> &SaldoId {
         &self.id
     }
+    pub fn lemgrams(&self) -> &[SaldoLemgramId] {
+        &self.lemgrams
+    }
+    /// The primary descriptor, `None` only for `PRIM..1`.
+    pub fn mf(&self) -> Option<&SaldoId> {
+        self.mf.as_ref()
+    }
+    /// The secondary descriptors, without `PRIM..1`.
+    pub fn pf(&self) -> &[SaldoId] {
+        &self.pf
+    }
+    /// The entries this is the primary descriptor of.
+    pub fn inv_mf(&self) -> &[SaldoId] {
+        &self.inv_mf
+    }
+    /// The entries this is a secondary descriptor of.
+    pub fn inv_pf(&self) -> &[SaldoId] {
+        &self.inv_pf
+    }
     pub fn set_mf(&mut self, mf: SaldoId) {
         self.mf = Some(mf);
     }
@@ -46,9 +46,15 @@ impl SaldoLemgram {
             entries: Vec::new(),
         }
     }
+    pub fn id(&self) -> &SaldoLemgramId {
+        &self.id
+    }
     pub fn pos(&self) -> &str {
         self.pos.as_str()
     }
+    pub fn entries(&self) -> &[SaldoId] {
+        &self.entries
+    }
     pub fn add_entry(&mut self, saldo_id: &SaldoId) {
         self.entries.push(saldo_id.clone());
     }
@@ -8,6 +8,7 @@ use parser_callback::SaldoParserCallback;
 
 use crate::{
     saldo_entry::{SaldoEntry, SaldoId},
+    saldo_lemgram::{SaldoLemgram, SaldoLemgramId},
     shared::xml_reader::XmlReader,
 };
 
@@ -16,6 +17,7 @@ mod parser_callback;
 #[derive(Debug, Clone)]
 pub struct SaldoLexicon {
     entries: HashMap<SaldoId, SaldoEntry>,
+    lemgrams: HashMap<SaldoLemgramId, SaldoLemgram>,
 }
 
 pub const PRIM: &str = "PRIM..1";
@@ -59,6 +61,7 @@ impl SaldoLexicon {
             mut entries,
             mfids,
             pfids,
+            lemgrams,
             ..
         } = reader.into_inner();
         log::debug!("Building graph ...");
@@ -97,6 +100,25 @@ impl SaldoLexicon {
         }
         log::info!("full size: {}", entries.len());
         log::info!(" Done.");
-        Ok(Self { entries })
+        Ok(Self { entries, lemgrams })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&SaldoEntry> {
+        self.entries.get(id)
+    }
+    pub fn contains(&self, id: &str) -> bool {
+        self.entries.contains_key(id)
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn entries(&self) -> impl Iterator<Item = &SaldoEntry> {
+        self.entries.values()
+    }
+    pub fn lemgrams(&self) -> impl Iterator<Item = &SaldoLemgram> {
+        self.lemgrams.values()
     }
 }
@@ -111,6 +111,12 @@ impl ContentHandler for SaldoParserCallback {
                     match entry_builder.build() {
                         Ok(entry) => {
                             let entry_id = entry.get_id().clone();
+                            // the sense id is only known once the whole entry is read
+                            for lemgram_id in entry.lemgrams() {
+                                if let Some(sl) = self.lemgrams.get_mut(lemgram_id) {
+                                    sl.add_entry(&entry_id);
+                                }
+                            }
                             self.all_entries.push(entry_id.clone());
                             self.entries.insert(entry_id, entry);
                         }
@@ -132,22 +138,16 @@ impl ContentHandler for SaldoParserCallback {
                             todo!("incompatible POS tags");
                         }
                         curr_entry.add_lemgram(&lemgram_id);
-                        if let Some(curr_entry_id) = curr_entry.get_id() {
-                            sl.add_entry(curr_entry_id);
-                        }
                     } else {
                         let Some(wf) = self.curr_wf.take() else {
                             todo!("no wf: {}", lemgram_id);
                         };
-                        let mut sl = SaldoLemgram::new(
+                        let sl = SaldoLemgram::new(
                             lemgram_id.clone(),
                             curr_pos,
                             self.curr_para.take(),
                             wf.clone(),
                         );
-                        if let Some(curr_entry_id) = curr_entry.get_id() {
-                            sl.add_entry(curr_entry_id);
-                        }
                         self.lemgrams.insert(lemgram_id.clone(), sl);
                         curr_entry.add_lemgram(&lemgram_id);
                         self.lemgrams_by_lemma.insert(wf, lemgram_id);
@@ -1,12 +1,15 @@
-use std::io;
+use std::{fs, io};
 
 use log::LevelFilter;
 use miette::IntoDiagnostic;
-use options::Args;
+use options::{Args, Format, Output};
 use saldo::SaldoLexicon;
 use wsd_application::{
-    SourceFormat, TabFormat, make_wsd_application,
-    wsd_application::{DisambiguateOptions, disambiguate_sentences, evaluate},
+    ConlluFormat, ConlluOutput, EnsembleWSD, GoldKey, JsonOutput, JsonlFormat, ModelBundle,
+    OutputFormat, SbXmlFormat, SbXmlOutput, SharedWSDApplication, SourceFormat, SparvOutput,
+    TabFormat, TabOutput, UsageError, VectorWSD, VectorWSDOptions, VrtFormat, VrtOutput, evaluate,
+    make_wsd_application, sense_ratios,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences_with_output},
 };
 
 mod options;
@@ -19,53 +22,214 @@ fn main() -> miette::Result<()> {
 
     configure_logging(args.verbose);
 
+    if let Some(dir) = &args.create_bundle {
+        create_bundle(dir, &args, &argv)?;
+        return Ok(());
+    }
+
     let saldo = match &args.saldo {
         None => None,
         Some(saldo_file) => Some(SaldoLexicon::new(saldo_file)?),
     };
 
-    let wsd = make_wsd_application(saldo.as_ref(), &args.app_name, &argv)?;
+    if args.check_models {
+        let wsd = VectorWSD::from_args_unchecked(&argv)?;
+        let report = wsd.check_models(saldo.as_ref());
+        print!("{}", report);
+        report.validate()?;
+        return Ok(());
+    }
 
-    if args.eval {
-        evaluate(wsd, &args.eval_lemmas.unwrap(), &args.eval_key.unwrap());
+    if let Some(neighbours) = &args.neighbours {
+        let wsd = VectorWSD::from_args(saldo.as_ref(), &argv)?;
+        let queries: Vec<&str> = neighbours.split(',').collect();
+        let mut stdout = io::stdout().lock();
+        wsd.write_neighbours(&queries, args.top_k, args.approximate, &mut stdout)
+            .into_diagnostic()?;
         return Ok(());
     }
 
-    // let mut ratios = None;
-    if args.for_lemma.is_some() {
-        todo!("ratios is not yet supported");
-        // todo!("ratios = Some(HashMap::new())");
+    let wsd = match args.tune_weights {
+        Some(step) => tune_ensemble(saldo.as_ref(), &args, &argv, step)?,
+        None => make_wsd_application(saldo.as_ref(), &args.app_name, &argv)?,
+    };
+
+    if args.eval {
+        let key = GoldKey::read(&mut open("-evalKey", args.eval_key.as_deref().unwrap())?)?;
+        let mut instances = open("-evalLemmas", args.eval_lemmas.as_deref().unwrap())?;
+        let mut format = TabFormat::with_columns(args.parse_mode, args.tab_columns.clone());
+        let opts = DisambiguateOptions {
+            batch_size: args.batch_size,
+            threads: args.threads,
+            ..DisambiguateOptions::default()
+        };
+        let report = evaluate(wsd, &mut instances, &mut format, &key, opts)?;
+        print!("{}", report);
+        if let Some(path) = &args.eval_report {
+            let mut file = fs::File::create(path).map_err(|source| UsageError::IoError {
+                param: "-evalReport".into(),
+                path: path.clone(),
+                source,
+            })?;
+            report.write_json(&mut file).into_diagnostic()?;
+        }
+        return Ok(());
     }
 
     let mut stdin = io::stdin().lock();
     let mut stdout = io::stdout().lock();
-    let format: Box<dyn SourceFormat> = if args.sbxml {
-        todo!("sbxml format is not yet supported");
-    } else {
-        Box::new(TabFormat::default())
+    let mut format: Box<dyn SourceFormat> = match args.format {
+        Format::Tab => Box::new(TabFormat::with_columns(
+            args.parse_mode,
+            args.tab_columns.clone(),
+        )),
+        Format::SbXml => Box::new(SbXmlFormat::new()),
+        Format::Conllu => Box::new(ConlluFormat::new(args.sense_key.as_str())),
+        Format::Vrt => Box::new(VrtFormat::new(args.vrt_attributes.clone())),
+        Format::Jsonl => Box::new(JsonlFormat::new()),
     };
-    disambiguate_sentences(
+    if let Some(for_lemma) = &args.for_lemma {
+        let targets: Vec<&str> = for_lemma.split(',').collect();
+        let ratios = sense_ratios(
+            wsd,
+            &mut stdin,
+            &mut format,
+            &targets,
+            args.batch_size,
+            args.max_sen,
+        )?;
+        print!("{}", ratios);
+        return Ok(());
+    }
+    let mut output: Option<Box<dyn OutputFormat>> = match args.output {
+        Output::Input => None,
+        Output::Tab => Some(Box::new(TabOutput)),
+        Output::Sparv => Some(Box::new(SparvOutput)),
+        Output::Json => Some(Box::new(JsonOutput)),
+        Output::Conllu => Some(Box::new(ConlluOutput::new(args.sense_key.as_str()))),
+        Output::Vrt => Some(Box::new(VrtOutput::new(args.vrt_attributes.clone()))),
+        Output::SbXml => Some(Box::new(SbXmlOutput::new())),
+    };
+    disambiguate_sentences_with_output(
         wsd,
         &mut stdin,
         &mut stdout,
-        &format,
+        &mut format,
+        output
+            .as_mut()
+            .map(|output| output as &mut dyn OutputFormat),
         DisambiguateOptions {
             batch_size: args.batch_size,
             max_sen: args.max_sen,
+            escape_output: args.escape_output,
+            threads: args.threads,
         },
-    )
-    .into_diagnostic()?;
+    )?;
+    Ok(())
+}
+
+fn open(param: &str, path: &str) -> Result<io::BufReader<fs::File>, UsageError> {
+    fs::File::open(path)
+        .map(io::BufReader::new)
+        .map_err(|source| UsageError::IoError {
+            param: param.into(),
+            path: path.into(),
+            source,
+        })
+}
 
-    if args.for_lemma.is_some() {
-        todo!("printRatios(ratios)");
+/// Tune the weights of the ensemble against the evaluation instances and
+/// print them as `-members=`.
+fn tune_ensemble(
+    saldo: Option<&SaldoLexicon>,
+    args: &Args,
+    argv: &[String],
+    step: f32,
+) -> miette::Result<SharedWSDApplication> {
+    if args.app_name != "EnsembleWSD" {
+        return Err(UsageError::BadValue {
+            param: "-appName (must be EnsembleWSD with -tuneWeights)".into(),
+            value: args.app_name.clone(),
+        }
+        .into());
     }
+    let mut ensemble = EnsembleWSD::from_args(saldo, argv)?;
+    let key = GoldKey::read(&mut open("-tuneKey", args.tune_key.as_deref().unwrap())?)?;
+    let mut instances = open("-tuneLemmas", args.tune_lemmas.as_deref().unwrap())?;
+    let mut format = TabFormat::with_columns(args.parse_mode, args.tab_columns.clone());
+    let documents = format.read_documents(&mut instances)?;
+    let scores = ensemble.tune(&documents, &key, step);
+    let weights: Vec<f32> = ensemble.members().iter().map(|m| m.weight).collect();
+    log::info!("Tuned weights: {:?}", weights);
+    log::info!(
+        "{}/{} tuning instances correct with the tuned weights",
+        scores.correct,
+        scores.instances
+    );
+    // not on stdout, which has the output of the tuned ensemble
+    eprintln!("-members={}", ensemble.members_spec());
+    Ok(std::sync::Arc::new(ensemble))
+}
 
-    // TODO split into chunks and use thread pool
+fn create_bundle(dir: &str, args: &Args, argv: &[String]) -> miette::Result<()> {
+    let flag = |name: &str| {
+        argv.iter()
+            .find_map(|a| a.strip_prefix(name))
+            .ok_or_else(|| UsageError::missing_required_argument(name))
+    };
+    let mut options = VectorWSDOptions::default();
+    options.update_from_args(argv)?;
+    let bundle = ModelBundle::create(
+        dir,
+        flag("-svFile=")?,
+        flag("-cvFile=")?,
+        flag("-idfFile=").ok(),
+        options.into(),
+        args.saldo_version.clone(),
+        args.training_corpus.clone(),
+    )?;
+    log::info!("Created model bundle: {:?}", bundle.manifest());
     Ok(())
 }
 
 fn usage() {
-    eprintln!("Usage: saldowsd -appName=APP_NAME [-saldo=SALDO]");
+    eprintln!(
+        "Usage: saldowsd -appName=APP_NAME [-saldo=SALDO] [-format=tab|sbxml|conllu|vrt|jsonl|eval] [-outputFormat=tab|sparv|json|sbxml|conllu|vrt|jsonl] [-parseMode=strict|lenient] [-batchSize=1] [-threads=1]"
+    );
+    eprintln!(
+        "       [-tabColumns=sparv|word:COL,pos:COL,lemgrams:COL,lemmas:COL,senses:COL] [-tabHeader=false] [-valueSeparator=|]"
+    );
+    eprintln!(
+        "       [-senseKey=Sense] [-positionalAttributes=word,pos,lemma,lex,sense] [-wordAttribute=word] [-lemmaAttribute=lex] [-senseAttribute=sense] [-scoresAttribute=wsd]"
+    );
+    eprintln!(
+        "       saldowsd -appName=APP_NAME -format=eval -evalLemmas=INSTANCES -evalKey=KEY [-evalReport=REPORT.json] [-tabColumns=..]"
+    );
+    eprintln!(
+        "       saldowsd -appName=EnsembleWSD -members=APP_NAME[:ALIAS][:WEIGHT],... [-combine=average|product|vote] [-saldo=SALDO] [-ALIAS.FLAG=..] [-tuneWeights=STEP -tuneLemmas=INSTANCES -tuneKey=KEY]"
+    );
+    eprintln!("       saldowsd -appName=APP_NAME -forLemma=LEMMA[,...] [-format=..] < CORPUS");
+    eprintln!(
+        "       saldowsd -appName=VectorWSD -svFile=SV -cvFile=CV [-kernel=uniform|linear|exponential[:RATE]|gaussian[:SIGMA]|inverse] [-leftWidth=..] [-rightWidth=..] [-idfFile=FREQS] [-contextSentences=0] [-documentWeight=0] [-saldo=SALDO -senseBackoff=true|PRIMARY,SECONDARY,INVERSE]"
+    );
+    eprintln!(
+        "       saldowsd -appName=PageRankWSD -saldo=SALDO [-damping=0.85] [-iterations=30] [-secondaryWeight=1] [-contextWidth=..] [-pageRankMode=word|sentence]"
+    );
+    eprintln!(
+        "       saldowsd -appName=FirstSenseWSD | -appName=MostFrequentSenseWSD -senseFreqFile=FREQS [-smoothing=0] | -appName=RandomWSD [-seed=0]"
+    );
+    eprintln!(
+        "       saldowsd -model=BUNDLE [-saldo=SALDO [-saldoVersion=..]] [-decay=..] [-s1Prior=..] [-contextWidth=..]"
+    );
+    eprintln!(
+        "       saldowsd -createBundle=DIR -svFile=SV -cvFile=CV -decay=.. -s1Prior=.. -contextWidth=.. [-kernel=..] [-leftWidth=..] [-rightWidth=..] [-contextSentences=..] [-documentWeight=..] [-idfFile=FREQS] [-saldoVersion=..] [-trainingCorpus=..]"
+    );
+    eprintln!(
+        "       saldowsd -appName=VectorWSD -svFile=SV -cvFile=CV [-saldo=SALDO] -checkModels"
+    );
+    eprintln!(
+        "       saldowsd -appName=VectorWSD -svFile=SV -cvFile=CV -neighbours=SENSE_OR_LEMMA[,...] [-topK=10] [-approximate=false]"
+    );
     eprintln!();
 }
 
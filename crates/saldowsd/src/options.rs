@@ -1,4 +1,29 @@
-use wsd_application::UsageError;
+use process_corpus::ParseMode;
+use wsd_application::{TabColumns, UsageError, VrtAttributes};
+
+/// The corpus formats `-format=` selects between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Tab,
+    SbXml,
+    Conllu,
+    Vrt,
+    Jsonl,
+}
+
+/// The output formats `-outputFormat=` selects between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// Write in the input format, keeping its markup
+    Input,
+    Tab,
+    Sparv,
+    Json,
+    /// Fresh markup, for input in another format
+    Conllu,
+    Vrt,
+    SbXml,
+}
 
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -7,23 +32,61 @@ pub struct Args {
     pub saldo: Option<String>,
     /// app-name to use
     pub app_name: String,
-    /// Format of the output
-    pub sbxml: bool,
+    /// Model bundle directory
+    pub model: Option<String>,
+    /// Create a model bundle in this directory and exit
+    pub create_bundle: Option<String>,
+    /// SALDO version recorded when creating a bundle
+    pub saldo_version: Option<String>,
+    /// Training corpus description recorded when creating a bundle
+    pub training_corpus: Option<String>,
+    /// Format of the input and output
+    pub format: Format,
+    /// Format of the output, if other than the input
+    pub output: Output,
+    /// The columns of tab-separated input
+    pub tab_columns: TabColumns,
+    /// The CoNLL-U MISC key holding the candidate senses
+    pub sense_key: String,
+    /// The VRT positional attributes to read and write
+    pub vrt_attributes: VrtAttributes,
     pub eval: bool,
+    /// Write non-ASCII output as `\xNN` escapes
+    pub escape_output: bool,
+    /// How malformed input lines are handled
+    pub parse_mode: ParseMode,
     /// Should MWEs be split?
     pub split_mwes: bool,
     /// Should Compunds be split?
     pub split_compounds: bool,
     /// The size of each batch
     pub batch_size: usize,
+    /// The number of worker threads, 0 for one per CPU
+    pub threads: usize,
     /// evalLemmas
     pub eval_lemmas: Option<String>,
     /// evalKey
     pub eval_key: Option<String>,
-    /// forLemma
+    /// Write the evaluation scores as JSON to this file
+    pub eval_report: Option<String>,
+    /// Tune the weights of an EnsembleWSD against the tuning key in steps of this size
+    pub tune_weights: Option<f32>,
+    /// tuneLemmas, the held-out instances to tune on
+    pub tune_lemmas: Option<String>,
+    /// tuneKey
+    pub tune_key: Option<String>,
+    /// Print the sense ratios of these comma-separated lemmas instead of disambiguating
     pub for_lemma: Option<String>,
     /// The maximum sense
     pub max_sen: usize,
+    /// Print the nearest neighbours of these comma-separated sense ids or lemmas
+    pub neighbours: Option<String>,
+    /// Number of neighbours to print
+    pub top_k: usize,
+    /// Use an approximate index for the neighbour search
+    pub approximate: bool,
+    /// Print a compatibility report for the VectorWSD models and exit
+    pub check_models: bool,
     /// Verbosity
     pub verbose: u8,
 }
@@ -32,15 +95,37 @@ impl Args {
     pub fn parse(argv: &[String]) -> Result<Self, UsageError> {
         let mut saldo = None;
         let mut app_name_opt = None;
-        let mut sbxml = true;
+        let mut model = None;
+        let mut create_bundle = None;
+        let mut saldo_version = None;
+        let mut training_corpus = None;
+        let mut format = Format::SbXml;
+        let mut output = Output::Input;
+        let mut output_markup = None;
+        let mut tab_columns = TabColumns::sparv();
+        let mut tab_header = None;
+        let mut value_separator = None;
+        let mut sense_key = wsd_application::DEFAULT_SENSE_KEY.to_string();
+        let mut vrt_attributes = VrtAttributes::default();
         let mut eval = false;
+        let mut escape_output = false;
+        let mut parse_mode = ParseMode::default();
         let mut split_mwes = false;
         let mut split_compounds = true;
         let mut batch_size = 1;
+        let mut threads = 1;
         let mut eval_lemmas = None;
         let mut eval_key = None;
+        let mut eval_report = None;
+        let mut tune_weights = None;
+        let mut tune_lemmas = None;
+        let mut tune_key = None;
         let mut max_sen = u32::MAX as usize;
         let mut for_lemma = None;
+        let mut neighbours = None;
+        let mut top_k = 10;
+        let mut approximate = false;
+        let mut check_models = false;
         let mut verbose = 0;
 
         for a in argv {
@@ -48,12 +133,81 @@ impl Args {
                 saldo = Some(saldo_file.to_string());
             } else if let Some(app_name) = a.strip_prefix("-appName=") {
                 app_name_opt = Some(app_name.to_string());
+            } else if let Some(val) = a.strip_prefix("-model=") {
+                model = Some(val.to_string());
+            } else if let Some(val) = a.strip_prefix("-createBundle=") {
+                create_bundle = Some(val.to_string());
+            } else if let Some(val) = a.strip_prefix("-saldoVersion=") {
+                saldo_version = Some(val.to_string());
+            } else if let Some(val) = a.strip_prefix("-trainingCorpus=") {
+                training_corpus = Some(val.to_string());
             } else if a == "-format=tab" {
-                sbxml = false;
+                format = Format::Tab;
             } else if a == "-format=sbxml" {
-                sbxml = true;
+                format = Format::SbXml;
+            } else if a == "-format=conllu" {
+                format = Format::Conllu;
+            } else if a == "-format=vrt" {
+                format = Format::Vrt;
+            } else if a == "-format=jsonl" {
+                format = Format::Jsonl;
+            } else if let Some(val) = a.strip_prefix("-outputFormat=") {
+                output = Output::Input;
+                output_markup = None;
+                match val {
+                    "tab" => output = Output::Tab,
+                    "sparv" => output = Output::Sparv,
+                    "json" => output = Output::Json,
+                    "sbxml" => output_markup = Some((Format::SbXml, Output::SbXml)),
+                    "conllu" => output_markup = Some((Format::Conllu, Output::Conllu)),
+                    "vrt" => output_markup = Some((Format::Vrt, Output::Vrt)),
+                    "jsonl" => output_markup = Some((Format::Jsonl, Output::Json)),
+                    _ => {
+                        return Err(UsageError::BadValue {
+                            param: "-outputFormat".into(),
+                            value: val.into(),
+                        });
+                    }
+                }
+            } else if let Some(val) = a.strip_prefix("-tabColumns=") {
+                tab_columns = val.parse().map_err(|_| UsageError::BadValue {
+                    param: "-tabColumns".into(),
+                    value: val.into(),
+                })?;
+            } else if let Some(val) = a.strip_prefix("-tabHeader=") {
+                tab_header = Some(val.parse().map_err(|_| UsageError::BadValue {
+                    param: "-tabHeader".into(),
+                    value: val.into(),
+                })?);
+            } else if let Some(val) = a.strip_prefix("-valueSeparator=") {
+                value_separator = Some(val.parse().map_err(|_| UsageError::BadValue {
+                    param: "-valueSeparator".into(),
+                    value: val.into(),
+                })?);
+            } else if let Some(val) = a.strip_prefix("-senseKey=") {
+                sense_key = val.to_string();
+            } else if let Some(val) = a.strip_prefix("-positionalAttributes=") {
+                vrt_attributes.positional = val.split(',').map(String::from).collect();
+            } else if let Some(val) = a.strip_prefix("-wordAttribute=") {
+                vrt_attributes.word = val.to_string();
+            } else if let Some(val) = a.strip_prefix("-lemmaAttribute=") {
+                vrt_attributes.lemma = val.to_string();
+            } else if let Some(val) = a.strip_prefix("-senseAttribute=") {
+                vrt_attributes.sense = val.to_string();
+            } else if let Some(val) = a.strip_prefix("-scoresAttribute=") {
+                vrt_attributes.scores = val.to_string();
             } else if a == "-format=eval" {
                 eval = true;
+            } else if let Some(val) = a.strip_prefix("-escapeOutput=") {
+                escape_output = val.parse().map_err(|_| UsageError::BadValue {
+                    param: "-escapeOutput".into(),
+                    value: val.into(),
+                })?;
+            } else if let Some(val) = a.strip_prefix("-parseMode=") {
+                parse_mode = val.parse().map_err(|_| UsageError::BadValue {
+                    param: "-parseMode".into(),
+                    value: val.into(),
+                })?;
             } else if let Some(val) = a.strip_prefix("-splitMWEs=") {
                 split_mwes = val.parse().map_err(|_| UsageError::BadValue {
                     param: "-splitMWEs".into(),
@@ -69,6 +223,11 @@ impl Args {
                     param: "-batchSize".into(),
                     value: val.into(),
                 })?;
+            } else if let Some(val) = a.strip_prefix("-threads=") {
+                threads = val.parse().map_err(|_| UsageError::BadValue {
+                    param: "-threads".into(),
+                    value: val.into(),
+                })?;
             } else if let Some(val) = a.strip_prefix("-maxSen=") {
                 max_sen = val.parse().map_err(|_| UsageError::BadValue {
                     param: "-maxSen".into(),
@@ -78,14 +237,62 @@ impl Args {
                 eval_lemmas = Some(val.to_string());
             } else if let Some(val) = a.strip_prefix("-evalKey=") {
                 eval_key = Some(val.to_string());
+            } else if let Some(val) = a.strip_prefix("-evalReport=") {
+                eval_report = Some(val.to_string());
+            } else if let Some(val) = a.strip_prefix("-tuneWeights=") {
+                tune_weights = Some(
+                    val.parse()
+                        .ok()
+                        .filter(|step: &f32| *step > 0.0 && *step <= 1.0)
+                        .ok_or_else(|| UsageError::BadValue {
+                            param: "-tuneWeights".into(),
+                            value: val.into(),
+                        })?,
+                );
+            } else if let Some(val) = a.strip_prefix("-tuneLemmas=") {
+                tune_lemmas = Some(val.to_string());
+            } else if let Some(val) = a.strip_prefix("-tuneKey=") {
+                tune_key = Some(val.to_string());
             } else if let Some(val) = a.strip_prefix("-forLemma=") {
                 for_lemma = Some(val.to_string());
+            } else if let Some(val) = a.strip_prefix("-neighbours=") {
+                neighbours = Some(val.to_string());
+            } else if let Some(val) = a.strip_prefix("-topK=") {
+                top_k = val.parse().map_err(|_| UsageError::BadValue {
+                    param: "-topK".into(),
+                    value: val.into(),
+                })?;
+            } else if let Some(val) = a.strip_prefix("-approximate=") {
+                approximate = val.parse().map_err(|_| UsageError::BadValue {
+                    param: "-approximate".into(),
+                    value: val.into(),
+                })?;
+            } else if a == "-checkModels" {
+                check_models = true;
             } else if a == "-verbose" || a == "-v" {
                 verbose += 1;
             } else if a == "-verbose=true" {
                 verbose = 2;
             }
         }
+        if let Some((markup, fresh)) = output_markup {
+            // the input format keeps its markup, others get fresh markup
+            output = if markup == format {
+                Output::Input
+            } else {
+                fresh
+            };
+        }
+        if output == Output::Json && format == Format::Jsonl {
+            // the same layout, but with the passthrough fields
+            output = Output::Input;
+        }
+        if let Some(header) = tab_header {
+            tab_columns.header = header;
+        }
+        if let Some(separator) = value_separator {
+            tab_columns.separator = separator;
+        }
         if eval && eval_lemmas.is_none() {
             return Err(UsageError::missing_required_argument(
                 "-evalLemmas not specified, required when --format=eval",
@@ -96,24 +303,58 @@ impl Args {
                 "-evalKey not specified, required when --format=eval",
             ));
         }
-        if eval && for_lemma.is_none() {
+        if let Some(for_lemma) = for_lemma.as_ref().filter(|_| eval) {
+            return Err(UsageError::BadValue {
+                param: "-forLemma (cannot be used with -format=eval)".into(),
+                value: for_lemma.clone(),
+            });
+        }
+        if tune_weights.is_some() && tune_lemmas.is_none() {
+            return Err(UsageError::missing_required_argument(
+                "-tuneLemmas not specified, required with -tuneWeights",
+            ));
+        }
+        if tune_weights.is_some() && tune_key.is_none() {
             return Err(UsageError::missing_required_argument(
-                "-forLemma not specified, required when --format=eval",
+                "-tuneKey not specified, required with -tuneWeights",
             ));
         }
+        if app_name_opt.is_none() && (model.is_some() || create_bundle.is_some()) {
+            // bundles always hold VectorWSD models
+            app_name_opt = Some("VectorWSD".to_string());
+        }
         Ok(Self {
             saldo,
             app_name: app_name_opt
                 .ok_or_else(|| UsageError::missing_required_argument("-appName not specified"))?,
-            sbxml,
+            model,
+            create_bundle,
+            saldo_version,
+            training_corpus,
+            format,
+            output,
+            tab_columns,
+            sense_key,
+            vrt_attributes,
             eval,
+            escape_output,
+            parse_mode,
             split_mwes,
             split_compounds,
             batch_size,
+            threads,
             eval_lemmas,
             eval_key,
+            eval_report,
+            tune_weights,
+            tune_lemmas,
+            tune_key,
             for_lemma,
             max_sen,
+            neighbours,
+            top_k,
+            approximate,
+            check_models,
             verbose,
         })
     }
@@ -0,0 +1,72 @@
+use hashbrown::HashMap;
+use ndarray::{Array1, CowArray, Ix1};
+
+/// A lookup from words (or sense ids) to their vectors.
+///
+/// Implemented by the in-memory map returned by [`read_w2v_file`](crate::word2vec2::read_w2v_file),
+/// by [`MatrixEmbeddings`](crate::MatrixEmbeddings) and by [`MmapEmbeddings`](crate::MmapEmbeddings).
+pub trait Embeddings {
+    /// The dimension of the vectors in this store.
+    fn dim(&self) -> usize;
+
+    /// The number of words in this store.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Look up the vector for `word`.
+    fn embedding(&self, word: &str) -> Option<CowArray<'_, f32, Ix1>>;
+
+    fn contains(&self, word: &str) -> bool {
+        self.embedding(word).is_some()
+    }
+
+    /// Iterate over all words in this store, in no particular order.
+    fn words(&self) -> Box<dyn Iterator<Item = &str> + '_>;
+}
+
+impl Embeddings for HashMap<String, Array1<f32>> {
+    fn dim(&self) -> usize {
+        self.values().next().map_or(0, |v| v.len())
+    }
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn embedding(&self, word: &str) -> Option<CowArray<'_, f32, Ix1>> {
+        self.get(word).map(CowArray::from)
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.contains_key(word)
+    }
+
+    fn words(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.keys().map(String::as_str))
+    }
+}
+
+impl<E: Embeddings + ?Sized> Embeddings for Box<E> {
+    fn dim(&self) -> usize {
+        self.as_ref().dim()
+    }
+
+    fn len(&self) -> usize {
+        self.as_ref().len()
+    }
+
+    fn embedding(&self, word: &str) -> Option<CowArray<'_, f32, Ix1>> {
+        self.as_ref().embedding(word)
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.as_ref().contains(word)
+    }
+
+    fn words(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        self.as_ref().words()
+    }
+}
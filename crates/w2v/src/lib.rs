@@ -1 +1,10 @@
+mod embeddings;
+mod matrix_embeddings;
+mod mmap_embeddings;
+mod neighbours;
 pub mod word2vec2;
+
+pub use self::embeddings::Embeddings;
+pub use self::matrix_embeddings::MatrixEmbeddings;
+pub use self::mmap_embeddings::MmapEmbeddings;
+pub use self::neighbours::{ExactIndex, LshIndex, NearestNeighbours, Neighbour, cosine};
@@ -0,0 +1,74 @@
+use std::io;
+
+use hashbrown::HashMap;
+use ndarray::{Array2, ArrayView2, CowArray, Ix1};
+
+use crate::Embeddings;
+
+/// Embeddings stored as the rows of one contiguous matrix.
+#[derive(Debug, Clone)]
+pub struct MatrixEmbeddings {
+    words: Vec<String>,
+    index: HashMap<String, usize>,
+    matrix: Array2<f32>,
+}
+
+impl MatrixEmbeddings {
+    /// Create a store where row `i` of `matrix` is the vector of `words[i]`.
+    ///
+    /// If a word occurs more than once, the first occurrence wins. Fails if
+    /// there is not one row per word.
+    pub fn new(words: Vec<String>, matrix: Array2<f32>) -> io::Result<Self> {
+        if words.len() != matrix.nrows() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} words but {} rows", words.len(), matrix.nrows()),
+            ));
+        }
+        let mut index = HashMap::with_capacity(words.len());
+        for (i, w) in words.iter().enumerate() {
+            index.entry(w.clone()).or_insert(i);
+        }
+        Ok(Self {
+            words,
+            index,
+            matrix,
+        })
+    }
+
+    pub fn matrix(&self) -> ArrayView2<'_, f32> {
+        self.matrix.view()
+    }
+
+    /// The row of `word` in [`matrix`](Self::matrix).
+    pub fn row_index(&self, word: &str) -> Option<usize> {
+        self.index.get(word).copied()
+    }
+
+    pub fn word(&self, row: usize) -> &str {
+        &self.words[row]
+    }
+}
+
+impl Embeddings for MatrixEmbeddings {
+    fn dim(&self) -> usize {
+        self.matrix.ncols()
+    }
+
+    fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    fn embedding(&self, word: &str) -> Option<CowArray<'_, f32, Ix1>> {
+        self.row_index(word)
+            .map(|i| CowArray::from(self.matrix.row(i)))
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.index.contains_key(word)
+    }
+
+    fn words(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.index.keys().map(String::as_str))
+    }
+}
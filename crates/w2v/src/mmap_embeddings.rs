@@ -0,0 +1,59 @@
+use std::{io, path::Path};
+
+use hashbrown::HashMap;
+use memmap2::Mmap;
+use ndarray::{Array1, CowArray, Ix1};
+
+use crate::{
+    Embeddings,
+    word2vec2::{FLOAT_NBYTES, RecordIndex, bytes_to_float, map_file},
+};
+
+/// Embeddings read lazily from a memory-mapped word2vec binary file.
+///
+/// Only the word index is kept in memory, each vector is decoded on lookup.
+#[derive(Debug)]
+pub struct MmapEmbeddings {
+    mmap: Mmap,
+    dim: usize,
+    offsets: HashMap<String, usize>,
+}
+
+impl MmapEmbeddings {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mmap = map_file(path)?;
+        let RecordIndex { dim, records } = RecordIndex::new(&mmap)?;
+
+        let mut offsets = HashMap::with_capacity(records.len());
+        for record in records {
+            offsets.entry(record.word(&mmap)?).or_insert(record.vector);
+        }
+        Ok(Self { mmap, dim, offsets })
+    }
+}
+
+impl Embeddings for MmapEmbeddings {
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    fn embedding(&self, word: &str) -> Option<CowArray<'_, f32, Ix1>> {
+        let offset = *self.offsets.get(word)?;
+        let v = (0..self.dim)
+            .map(|j| bytes_to_float(&self.mmap, offset + j * FLOAT_NBYTES))
+            .collect();
+        Some(CowArray::from(Array1::from_vec(v)))
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.offsets.contains_key(word)
+    }
+
+    fn words(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.offsets.keys().map(String::as_str))
+    }
+}
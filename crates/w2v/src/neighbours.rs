@@ -0,0 +1,129 @@
+use hashbrown::{HashMap, HashSet};
+use ndarray::{Array2, ArrayView1};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::Embeddings;
+
+/// A word found by a nearest-neighbour search, with its cosine similarity to the query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Neighbour {
+    pub word: String,
+    pub score: f32,
+}
+
+pub trait NearestNeighbours {
+    /// Return the `k` words most similar to `query`, best first.
+    fn nearest(&self, query: ArrayView1<'_, f32>, k: usize) -> Vec<Neighbour>;
+}
+
+/// Exact top-k cosine search by scanning every vector in the store.
+pub struct ExactIndex<'a> {
+    store: &'a dyn Embeddings,
+}
+
+impl<'a> ExactIndex<'a> {
+    pub fn new(store: &'a dyn Embeddings) -> Self {
+        Self { store }
+    }
+}
+
+impl NearestNeighbours for ExactIndex<'_> {
+    fn nearest(&self, query: ArrayView1<'_, f32>, k: usize) -> Vec<Neighbour> {
+        top_k(self.store, query, self.store.words(), k)
+    }
+}
+
+/// Approximate cosine search using random-hyperplane locality sensitive hashing.
+///
+/// Every table hashes a vector to the sign pattern of its dot products with
+/// `num_bits` random hyperplanes. Candidates sharing a bucket with the query in
+/// any table are then ranked exactly.
+pub struct LshIndex<'a> {
+    store: &'a dyn Embeddings,
+    planes: Vec<Array2<f32>>,
+    tables: Vec<HashMap<u64, Vec<&'a str>>>,
+}
+
+impl<'a> LshIndex<'a> {
+    pub fn build(store: &'a dyn Embeddings, num_tables: usize, num_bits: usize, seed: u64) -> Self {
+        assert!(num_bits <= 64, "at most 64 bits per hash");
+        let mut rng = StdRng::seed_from_u64(seed);
+        let dim = store.dim();
+        let planes: Vec<Array2<f32>> = (0..num_tables)
+            .map(|_| Array2::from_shape_simple_fn((num_bits, dim), || rng.random_range(-1.0..1.0)))
+            .collect();
+        let mut tables = vec![HashMap::new(); num_tables];
+        for word in store.words() {
+            let Some(v) = store.embedding(word) else {
+                continue;
+            };
+            for (table, planes) in tables.iter_mut().zip(&planes) {
+                table
+                    .entry(hash(planes, v.view()))
+                    .or_insert_with(Vec::new)
+                    .push(word);
+            }
+        }
+        Self {
+            store,
+            planes,
+            tables,
+        }
+    }
+}
+
+impl NearestNeighbours for LshIndex<'_> {
+    fn nearest(&self, query: ArrayView1<'_, f32>, k: usize) -> Vec<Neighbour> {
+        let mut candidates = HashSet::new();
+        for (table, planes) in self.tables.iter().zip(&self.planes) {
+            if let Some(bucket) = table.get(&hash(planes, query)) {
+                candidates.extend(bucket.iter().copied());
+            }
+        }
+        top_k(self.store, query, Box::new(candidates.into_iter()), k)
+    }
+}
+
+fn hash(planes: &Array2<f32>, v: ArrayView1<'_, f32>) -> u64 {
+    planes
+        .dot(&v)
+        .iter()
+        .enumerate()
+        .fold(0, |h, (i, x)| if *x >= 0.0 { h | (1 << i) } else { h })
+}
+
+/// The cosine similarity of `a` and `b`, or `None` if either is the zero vector.
+pub fn cosine(a: ArrayView1<'_, f32>, b: ArrayView1<'_, f32>) -> Option<f32> {
+    let norms = a.dot(&a).sqrt() * b.dot(&b).sqrt();
+    if norms == 0.0 {
+        return None;
+    }
+    Some(a.dot(&b) / norms)
+}
+
+fn top_k<'w>(
+    store: &dyn Embeddings,
+    query: ArrayView1<'_, f32>,
+    words: Box<dyn Iterator<Item = &'w str> + 'w>,
+    k: usize,
+) -> Vec<Neighbour> {
+    let mut scored: Vec<(&str, f32)> = words
+        .filter_map(|w| {
+            let v = store.embedding(w)?;
+            cosine(query, v.view()).map(|score| (w, score))
+        })
+        .collect();
+    let by_score = |a: &(&str, f32), b: &(&str, f32)| b.1.total_cmp(&a.1).then(a.0.cmp(b.0));
+    if k < scored.len() {
+        scored.select_nth_unstable_by(k, by_score);
+        scored.truncate(k);
+    }
+    scored.sort_unstable_by(by_score);
+    scored
+        .into_iter()
+        .map(|(word, score)| Neighbour {
+            word: word.to_string(),
+            score,
+        })
+        .collect()
+}
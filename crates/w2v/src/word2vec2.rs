@@ -1,82 +1,300 @@
 use std::{
     fs,
     io::{self, Read},
+    ops::Range,
 };
 
 use hashbrown::HashMap;
-use ndarray::Array1;
+use memmap2::Mmap;
+use ndarray::{Array1, Array2};
+use rayon::prelude::*;
+
+use crate::MatrixEmbeddings;
 
 const BUF_SIZE: usize = 1 << 20;
-const FLOAT_NBYTES: usize = 4;
+pub(crate) const FLOAT_NBYTES: usize = 4;
 const RELOAD_POS: usize = (0.9 * BUF_SIZE as f64) as usize;
 
 pub fn read_w2v_file(path: &str, normalize: bool) -> io::Result<HashMap<String, Array1<f32>>> {
-    let mut reader = io::BufReader::new(fs::File::open(path)?);
+    let mut dict = HashMap::new();
+    read_w2v_records(
+        path,
+        normalize,
+        |_dim| (),
+        |w, v| {
+            if dict.contains_key(&w) {
+                // Warning?
+            } else {
+                dict.insert(w, Array1::from_vec(v));
+            }
+        },
+    )?;
+    Ok(dict)
+}
 
-    let mut buf = vec![0; BUF_SIZE];
-    let mut num_buf = vec![];
+/// Read a word2vec binary file into a single row-major matrix.
+pub fn read_w2v_matrix(path: &str, normalize: bool) -> io::Result<MatrixEmbeddings> {
+    let mut dim = 0;
+    let mut words = Vec::new();
+    let mut data = Vec::new();
+    read_w2v_records(
+        path,
+        normalize,
+        |d| dim = d,
+        |w, v| {
+            words.push(w);
+            data.extend(v);
+        },
+    )?;
+    let matrix = Array2::from_shape_vec((words.len(), dim), data)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    MatrixEmbeddings::new(words, matrix)
+}
 
-    let mut nbuf = reader.read(&mut buf)?;
-    let mut pos = 0;
-    let mut mark = 0;
+/// Like [`read_w2v_file`], but decodes the vectors on all threads of the current rayon pool.
+///
+/// The result is identical to the one from [`read_w2v_file`].
+pub fn read_w2v_file_parallel(
+    path: &str,
+    normalize: bool,
+) -> io::Result<HashMap<String, Array1<f32>>> {
+    check_normalize(normalize)?;
+    let mmap = map_file(path)?;
+    let index = RecordIndex::new(&mmap)?;
+    let chunk_size = index.chunk_size();
+    let records: Vec<(String, Array1<f32>)> = index
+        .records
+        .par_chunks(chunk_size)
+        .flat_map_iter(|chunk| {
+            chunk.iter().map(|record| {
+                let w = record.word(&mmap)?;
+                let v = record.vector(&mmap, index.dim);
+                Ok((w, Array1::from_vec(v)))
+            })
+        })
+        .collect::<io::Result<_>>()?;
 
-    while buf[pos] >= b'0' && buf[pos] <= b'9' {
-        pos += 1;
-        num_buf.push(buf[pos]);
+    let mut dict = HashMap::with_capacity(records.len());
+    for (w, v) in records {
+        // keep the first vector of a word, like read_w2v_file
+        dict.entry(w).or_insert(v);
     }
+    Ok(dict)
+}
 
-    let voc_size: usize = String::from_utf8_lossy(&buf[mark..pos]).parse().unwrap();
+/// Like [`read_w2v_matrix`], but decodes the rows on all threads of the current rayon pool.
+///
+/// The result is identical to the one from [`read_w2v_matrix`].
+pub fn read_w2v_matrix_parallel(path: &str, normalize: bool) -> io::Result<MatrixEmbeddings> {
+    check_normalize(normalize)?;
+    let mmap = map_file(path)?;
+    let index = RecordIndex::new(&mmap)?;
+    let dim = index.dim;
+    let chunk_size = index.chunk_size();
+    let mut data = vec![0f32; index.records.len() * dim];
+    let words: Vec<String> = if dim == 0 {
+        index
+            .records
+            .iter()
+            .map(|record| record.word(&mmap))
+            .collect::<io::Result<_>>()?
+    } else {
+        data.par_chunks_mut(chunk_size * dim)
+            .zip(index.records.par_chunks(chunk_size))
+            .flat_map_iter(|(rows, chunk)| {
+                rows.chunks_mut(dim).zip(chunk).map(|(row, record)| {
+                    row.copy_from_slice(&record.vector(&mmap, dim));
+                    record.word(&mmap)
+                })
+            })
+            .collect::<io::Result<_>>()?
+    };
+    let matrix = Array2::from_shape_vec((words.len(), dim), data)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    MatrixEmbeddings::new(words, matrix)
+}
 
-    pos += 1;
-    mark = pos;
-    while buf[pos] >= b'0' && buf[pos] <= b'9' {
-        pos += 1;
+pub(crate) fn map_file(path: impl AsRef<std::path::Path>) -> io::Result<Mmap> {
+    let file = fs::File::open(path)?;
+    // SAFETY: the model files are treated as read-only while they are mapped.
+    unsafe { Mmap::map(&file) }
+}
+
+/// The positions of all records in a word2vec binary file.
+pub(crate) struct RecordIndex {
+    pub(crate) dim: usize,
+    pub(crate) records: Vec<Record>,
+}
+
+pub(crate) struct Record {
+    word: Range<usize>,
+    /// Position of the first float of the vector.
+    pub(crate) vector: usize,
+}
+
+impl RecordIndex {
+    /// Find where every record starts.
+    ///
+    /// Only the words are scanned, the fixed-size vectors are skipped.
+    pub(crate) fn new(buf: &[u8]) -> io::Result<Self> {
+        let (voc_size, dim, mut pos) = parse_header(buf)?;
+        let record_size = dim * FLOAT_NBYTES;
+
+        let mut records = Vec::with_capacity(voc_size);
+        for _ in 0..voc_size {
+            if buf.get(pos) == Some(&b'\n') {
+                pos += 1;
+            }
+            let mark = pos;
+            while buf.get(pos).is_some_and(|b| *b != b' ') {
+                pos += 1;
+            }
+            let word = mark..pos;
+            pos += 1;
+            if pos + record_size > buf.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "word2vec file is truncated",
+                ));
+            }
+            records.push(Record { word, vector: pos });
+            pos += record_size;
+        }
+        Ok(Self { dim, records })
     }
 
-    let dim: usize = String::from_utf8_lossy(&buf[mark..pos]).parse().unwrap();
+    /// Split the records in about four chunks per thread.
+    fn chunk_size(&self) -> usize {
+        let num_chunks = 4 * rayon::current_num_threads();
+        self.records.len().div_ceil(num_chunks).max(1)
+    }
+}
 
-    let mut dict = HashMap::new();
+impl Record {
+    pub(crate) fn word(&self, buf: &[u8]) -> io::Result<String> {
+        String::from_utf8(buf[self.word.clone()].to_vec())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn vector(&self, buf: &[u8], dim: usize) -> Vec<f32> {
+        (0..dim)
+            .map(|j| bytes_to_float(buf, self.vector + j * FLOAT_NBYTES))
+            .collect()
+    }
+}
+
+/// Parse a word2vec binary file, calling `on_record` for each word and its vector.
+///
+/// `on_header` receives the vector dimension before the first record.
+fn read_w2v_records(
+    path: &str,
+    normalize: bool,
+    on_header: impl FnOnce(usize),
+    mut on_record: impl FnMut(String, Vec<f32>),
+) -> io::Result<()> {
+    check_normalize(normalize)?;
+    let mut reader = io::BufReader::new(fs::File::open(path)?);
+
+    let mut buf = vec![0; BUF_SIZE];
+
+    let mut nbuf = fill(&mut reader, &mut buf)?;
+    let (voc_size, dim, mut pos) = parse_header(&buf[..nbuf])?;
+    on_header(dim);
+    let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "word2vec file is truncated");
 
     for _ in 0..voc_size {
         if pos > RELOAD_POS {
+            buf.copy_within(pos..nbuf, 0);
             nbuf -= pos;
-            buf.copy_within(pos.., 0);
-            let n = reader.read(&mut buf[nbuf..])?;
-            if n > 0 {
-                nbuf += n;
-            }
+            nbuf += fill(&mut reader, &mut buf[nbuf..])?;
             pos = 0;
         }
-        if buf[pos] == b'\n' {
+        if pos < nbuf && buf[pos] == b'\n' {
             pos += 1;
         }
-        mark = pos;
-        while buf[pos] != b' ' {
+        let mark = pos;
+        while pos < nbuf && buf[pos] != b' ' {
             pos += 1;
         }
-        let w = String::from_utf8(buf[mark..pos].to_vec()).expect("valid utf-8");
+        if pos >= nbuf {
+            return Err(truncated());
+        }
+        let w = String::from_utf8(buf[mark..pos].to_vec())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
         pos += 1;
-        let mut v = vec![0f32; dim];
-        for j in 0..dim {
-            v[j] = bytes_to_float(&buf, pos);
+        if pos + dim * FLOAT_NBYTES > nbuf {
+            return Err(truncated());
+        }
+        let mut v = Vec::with_capacity(dim);
+        for _ in 0..dim {
+            v.push(bytes_to_float(&buf, pos));
             pos += FLOAT_NBYTES;
         }
 
-        if normalize {
-            todo!("normalize is not supported yet")
-        }
+        on_record(w, v);
+    }
+    Ok(())
+}
 
-        if dict.contains_key(&w) {
-            // Warning?
-        } else {
-            dict.insert(w, Array1::from_vec(v));
+/// Read into `buf` until it is full or the input ends, returning the number of bytes read.
+fn fill(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(k) => n += k,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
         }
     }
-    Ok(dict)
+    Ok(n)
+}
+
+/// Fail if the vectors are to be normalized, which is not supported yet.
+fn check_normalize(normalize: bool) -> io::Result<()> {
+    if normalize {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "normalizing word2vec vectors is not supported",
+        ));
+    }
+    Ok(())
+}
+
+/// Parse the `"<voc_size> <dim>"` header, returning both and the position after it.
+pub(crate) fn parse_header(buf: &[u8]) -> io::Result<(usize, usize, usize)> {
+    // the position after the digits from `start`, which must not end the buffer
+    let end_of_number = |start: usize| -> io::Result<usize> {
+        let mut pos = start;
+        while buf.get(pos).is_some_and(u8::is_ascii_digit) {
+            pos += 1;
+        }
+        if pos >= buf.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "word2vec header is truncated",
+            ));
+        }
+        Ok(pos)
+    };
+    let mut pos = end_of_number(0)?;
+    let voc_size = parse_number(&buf[..pos])?;
+
+    pos += 1;
+    let mark = pos;
+    pos = end_of_number(mark)?;
+    let dim = parse_number(&buf[mark..pos])?;
+    Ok((voc_size, dim, pos))
+}
+
+fn parse_number(bytes: &[u8]) -> io::Result<usize> {
+    String::from_utf8_lossy(bytes)
+        .parse()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
 }
 
-fn bytes_to_float(buf: &[u8], pos: usize) -> f32 {
+pub(crate) fn bytes_to_float(buf: &[u8], pos: usize) -> f32 {
     let b0 = (buf[pos] as u32) & 255;
     let b1 = (buf[pos + 1] as u32) & 255;
     let b2 = (buf[pos + 2] as u32) & 255;
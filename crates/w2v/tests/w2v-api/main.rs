@@ -0,0 +1,2 @@
+mod test_embeddings;
+mod test_neighbours;
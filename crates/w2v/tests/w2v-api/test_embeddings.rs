@@ -0,0 +1,87 @@
+use rstest::rstest;
+use w2v::{Embeddings, MatrixEmbeddings, MmapEmbeddings, word2vec2};
+
+const SENSE_MODEL: &str = "assets/testing/models/sense.bin";
+
+#[rstest]
+#[case::matrix(Box::new(word2vec2::read_w2v_matrix(SENSE_MODEL, false).unwrap()))]
+#[case::mmap(Box::new(MmapEmbeddings::open(SENSE_MODEL).unwrap()))]
+fn test_stores_agree_with_map(#[case] store: Box<dyn Embeddings>) -> eyre::Result<()> {
+    let map = word2vec2::read_w2v_file(SENSE_MODEL, false)?;
+
+    assert_eq!(store.dim(), map.dim());
+    assert_eq!(store.len(), map.len());
+    for word in map.words() {
+        assert_eq!(store.embedding(word), map.embedding(word), "word={}", word);
+    }
+    assert!(store.embedding("anslag..3").is_none());
+    Ok(())
+}
+
+#[rstest]
+#[case::one_thread(1)]
+#[case::four_threads(4)]
+fn test_parallel_readers_match_sequential(#[case] num_threads: usize) -> eyre::Result<()> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()?;
+
+    let (map, matrix) = pool.install(|| {
+        (
+            word2vec2::read_w2v_file_parallel(SENSE_MODEL, false),
+            word2vec2::read_w2v_matrix_parallel(SENSE_MODEL, false),
+        )
+    });
+
+    assert_eq!(map?, word2vec2::read_w2v_file(SENSE_MODEL, false)?);
+    let (matrix, expected) = (matrix?, word2vec2::read_w2v_matrix(SENSE_MODEL, false)?);
+    assert_eq!(matrix.matrix(), expected.matrix());
+    for row in 0..expected.matrix().nrows() {
+        assert_eq!(matrix.word(row), expected.word(row));
+    }
+    Ok(())
+}
+
+#[rstest]
+#[case::empty("assets/testing/models/empty.bin")]
+#[case::header_only("assets/testing/models/header_only.bin")]
+#[case::cut_word("assets/testing/models/cut_word.bin")]
+#[case::mid_vector("assets/testing/models/mid_vector.bin")]
+fn test_truncated_files_fail(#[case] path: &str) {
+    let errors = [
+        MmapEmbeddings::open(path).err(),
+        word2vec2::read_w2v_file(path, false).err(),
+        word2vec2::read_w2v_matrix(path, false).err(),
+        word2vec2::read_w2v_file_parallel(path, false).err(),
+        word2vec2::read_w2v_matrix_parallel(path, false).err(),
+    ];
+
+    for err in errors {
+        let kind = err.expect("a truncated file is an error").kind();
+        assert_eq!(kind, std::io::ErrorKind::UnexpectedEof);
+    }
+}
+
+#[test]
+fn test_normalize_is_unsupported() {
+    let errors = [
+        word2vec2::read_w2v_file(SENSE_MODEL, true).err(),
+        word2vec2::read_w2v_file_parallel(SENSE_MODEL, true).err(),
+        word2vec2::read_w2v_matrix_parallel(SENSE_MODEL, true).err(),
+    ];
+
+    for err in errors {
+        let kind = err.expect("normalize is an error").kind();
+        assert_eq!(kind, std::io::ErrorKind::Unsupported);
+    }
+}
+
+#[test]
+fn test_matrix_needs_a_row_per_word() {
+    let err = MatrixEmbeddings::new(vec!["a".into()], ndarray::Array2::zeros((2, 3))).err();
+
+    assert_eq!(
+        err.expect("a missing word is an error").kind(),
+        std::io::ErrorKind::InvalidData
+    );
+}
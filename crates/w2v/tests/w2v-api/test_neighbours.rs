@@ -0,0 +1,35 @@
+use w2v::{Embeddings, ExactIndex, LshIndex, NearestNeighbours, word2vec2};
+
+const SENSE_MODEL: &str = "assets/testing/models/sense.bin";
+
+#[test]
+fn test_exact_search_finds_query_first() -> eyre::Result<()> {
+    let map = word2vec2::read_w2v_file(SENSE_MODEL, false)?;
+    let index = ExactIndex::new(&map);
+    let query = map.embedding("utskott..2").expect("sense has a vector");
+
+    let actual = index.nearest(query.view(), 5);
+
+    assert_eq!(actual.len(), 5);
+    assert_eq!(actual[0].word, "utskott..2");
+    assert!((actual[0].score - 1.0).abs() < 1e-6);
+    assert!(actual.windows(2).all(|w| w[0].score >= w[1].score));
+    Ok(())
+}
+
+#[test]
+fn test_lsh_search_scores_match_exact() -> eyre::Result<()> {
+    let map = word2vec2::read_w2v_file(SENSE_MODEL, false)?;
+    let exact = ExactIndex::new(&map);
+    let lsh = LshIndex::build(&map, 8, 6, 42);
+    let query = map.embedding("anslag..1").expect("sense has a vector");
+
+    let approximate = lsh.nearest(query.view(), 5);
+    let all = exact.nearest(query.view(), map.len());
+
+    assert_eq!(approximate[0].word, "anslag..1");
+    for n in &approximate {
+        assert!(all.contains(n), "{:?} not found by exact search", n);
+    }
+    Ok(())
+}
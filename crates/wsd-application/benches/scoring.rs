@@ -0,0 +1,102 @@
+//! Compare scoring each token on its own with scoring whole sentences.
+//!
+//! Run with `cargo bench -p wsd-application --bench scoring`.
+
+use std::{hint::black_box, time::Instant};
+
+use hashbrown::HashMap;
+use ndarray::Array1;
+use process_corpus::LemmaToken;
+use wsd_application::{VectorWSD, VectorWSDOptions, WSDApplication};
+
+const DIM: usize = 256;
+const NUM_LEMMAS: usize = 2000;
+const NUM_SENSES: usize = 3;
+const NUM_SENTENCES: usize = 200;
+const SENTENCE_LEN: usize = 30;
+
+/// A xorshift generator, so that every run scores the same data.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn vector(&mut self) -> Array1<f32> {
+        Array1::from_shape_fn(DIM, |_| (self.next() % 2001) as f32 / 1000.0 - 1.0)
+    }
+}
+
+fn main() {
+    let mut rng = Rng(0x5eed);
+    let mut sense_vectors = HashMap::new();
+    let mut ctx_vectors = HashMap::new();
+    for l in 0..NUM_LEMMAS {
+        ctx_vectors.insert(format!("lemma{}..nn", l), rng.vector());
+        for s in 1..=NUM_SENSES {
+            sense_vectors.insert(format!("lemma{}..{}", l, s), rng.vector());
+        }
+    }
+    let wsd = VectorWSD::new(
+        VectorWSDOptions {
+            decay: true,
+            s1prior: 1.0,
+            context_width: 10,
+            ..VectorWSDOptions::default()
+        },
+        Box::new(sense_vectors),
+        Box::new(ctx_vectors),
+    );
+    let text: Vec<Vec<LemmaToken>> = (0..NUM_SENTENCES)
+        .map(|_| {
+            (0..SENTENCE_LEN)
+                .map(|i| {
+                    let l = rng.next() as usize % NUM_LEMMAS;
+                    LemmaToken::new(
+                        (i + 1).to_string(),
+                        format!("word{}", l),
+                        vec![],
+                        vec![format!("lemma{}..nn", l)],
+                        (1..=NUM_SENSES)
+                            .map(|s| format!("lemma{}..{}", l, s))
+                            .collect(),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+
+    let start = Instant::now();
+    for lts in &text {
+        for i in 0..lts.len() {
+            black_box(wsd.disambiguate(lts, i));
+        }
+    }
+    let per_token = start.elapsed();
+
+    let start = Instant::now();
+    for lts in &text {
+        black_box(wsd.disambiguate_sentence(lts.clone()));
+    }
+    let per_sentence = start.elapsed();
+
+    let num_tokens = NUM_SENTENCES * SENTENCE_LEN;
+    println!(
+        "per token:    {:>10.3?} ({:.2?}/token)",
+        per_token,
+        per_token / num_tokens as u32
+    );
+    println!(
+        "per sentence: {:>10.3?} ({:.2?}/token)",
+        per_sentence,
+        per_sentence / num_tokens as u32
+    );
+    println!(
+        "speedup:      {:.2}x",
+        per_token.as_secs_f64() / per_sentence.as_secs_f64()
+    );
+}
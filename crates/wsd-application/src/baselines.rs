@@ -0,0 +1,241 @@
+use std::{collections::HashMap, fs, io};
+
+use process_corpus::LemmaToken;
+use saldo::SaldoLexicon;
+
+use crate::{SharedWSDApplication, UsageError, WSDApplication};
+
+/// The sense number of `anslag..2`, or `None` if there is none.
+pub(crate) fn sense_number(sense: &str) -> Option<u32> {
+    sense.rsplit_once("..")?.1.parse().ok()
+}
+
+/// All probability on the sense at `best`.
+fn one_hot(len: usize, best: usize) -> Vec<f32> {
+    let mut out = vec![0f32; len];
+    out[best] = 1.0;
+    out
+}
+
+/// The index of the lowest-numbered sense, the first one on ties.
+fn first_sense(senses: &[String]) -> usize {
+    senses
+        .iter()
+        .enumerate()
+        .min_by_key(|(i, sense)| (sense_number(sense).unwrap_or(u32::MAX), *i))
+        .map_or(0, |(i, _)| i)
+}
+
+/// Picks the lowest-numbered sense, like `anslag..1`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FirstSenseWSD;
+
+impl FirstSenseWSD {
+    pub fn new_as_shared(
+        _saldo: Option<&SaldoLexicon>,
+        _argv: &[String],
+    ) -> Result<SharedWSDApplication, UsageError> {
+        Ok(std::sync::Arc::new(Self))
+    }
+}
+
+impl WSDApplication for FirstSenseWSD {
+    fn disambiguate(&self, lts: &[LemmaToken], i: usize) -> Option<Vec<f32>> {
+        let senses = lts[i].possible_senses();
+        if senses.len() < 2 {
+            return None;
+        }
+        Some(one_hot(senses.len(), first_sense(senses)))
+    }
+}
+
+/// How often each sense occurs, from annotated data or sense ratios.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SenseFrequencies {
+    frequencies: HashMap<String, f64>,
+}
+
+impl SenseFrequencies {
+    pub fn from_counts<S: Into<String>>(counts: impl IntoIterator<Item = (S, f64)>) -> Self {
+        let mut frequencies = HashMap::new();
+        for (sense, n) in counts {
+            *frequencies.entry(sense.into()).or_default() += n;
+        }
+        Self { frequencies }
+    }
+
+    /// Read lines like `anslag..1 17`, adding the counts of repeated senses.
+    ///
+    /// The tables written by `-forLemma=` can be read as well; their
+    /// expected ratio is taken as the frequency. Blank lines and lines
+    /// starting with `#` are skipped.
+    pub fn read(reader: &mut dyn io::BufRead) -> io::Result<Self> {
+        let mut counts = Vec::new();
+        for (i, line) in io::BufRead::lines(reader).enumerate() {
+            let line = line?;
+            let text = line.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = text.split_whitespace().collect();
+            // the header lines of a sense ratio table
+            if fields[0] == "sense" || fields.iter().any(|f| f.starts_with("occurrences=")) {
+                continue;
+            }
+            match fields[..] {
+                [sense, n] | [sense, n, _] => match n.parse::<f64>() {
+                    Ok(n) if n >= 0.0 => counts.push((sense.to_string(), n)),
+                    _ => return Err(bad_line(i, &line)),
+                },
+                _ => return Err(bad_line(i, &line)),
+            }
+        }
+        Ok(Self::from_counts(counts))
+    }
+
+    pub fn get(&self, sense: &str) -> f64 {
+        self.frequencies.get(sense).copied().unwrap_or_default()
+    }
+}
+
+fn bad_line(i: usize, line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: expected 'SENSE COUNT', found '{}'", i + 1, line),
+    )
+}
+
+/// Gives each sense a probability in proportion to its frequency, plus
+/// `smoothing`, and falls back to [`FirstSenseWSD`] for lemmas none of whose
+/// senses have been seen.
+#[derive(Debug, Clone)]
+pub struct MostFrequentSenseWSD {
+    frequencies: SenseFrequencies,
+    smoothing: f64,
+}
+
+impl MostFrequentSenseWSD {
+    pub fn new(frequencies: SenseFrequencies, smoothing: f64) -> Self {
+        Self {
+            frequencies,
+            smoothing,
+        }
+    }
+
+    pub fn new_as_shared(
+        saldo: Option<&SaldoLexicon>,
+        argv: &[String],
+    ) -> Result<SharedWSDApplication, UsageError> {
+        Ok(std::sync::Arc::new(Self::from_args(saldo, argv)?))
+    }
+
+    /// Create from `-senseFreqFile=` and `-smoothing=`.
+    pub fn from_args(_saldo: Option<&SaldoLexicon>, argv: &[String]) -> Result<Self, UsageError> {
+        let mut path = None;
+        let mut smoothing = 0.0;
+        for a in argv {
+            if let Some(val) = a.strip_prefix("-senseFreqFile=") {
+                path = Some(val);
+            } else if let Some(val) = a.strip_prefix("-smoothing=") {
+                smoothing = val
+                    .parse()
+                    .ok()
+                    .filter(|s: &f64| *s >= 0.0)
+                    .ok_or_else(|| UsageError::BadValue {
+                        param: "-smoothing=".into(),
+                        value: val.to_string(),
+                    })?;
+            }
+        }
+        let path = path.ok_or_else(|| UsageError::missing_required_argument("-senseFreqFile="))?;
+        let frequencies = fs::File::open(path)
+            .and_then(|file| SenseFrequencies::read(&mut io::BufReader::new(file)))
+            .map_err(|source| UsageError::IoError {
+                param: "-senseFreqFile=".into(),
+                path: path.to_string(),
+                source,
+            })?;
+        Ok(Self::new(frequencies, smoothing))
+    }
+}
+
+impl WSDApplication for MostFrequentSenseWSD {
+    fn disambiguate(&self, lts: &[LemmaToken], i: usize) -> Option<Vec<f32>> {
+        let senses = lts[i].possible_senses();
+        if senses.len() < 2 {
+            return None;
+        }
+        let counts: Vec<f64> = senses.iter().map(|s| self.frequencies.get(s)).collect();
+        if counts.iter().all(|&n| n == 0.0) {
+            return Some(one_hot(senses.len(), first_sense(senses)));
+        }
+        let total: f64 = counts.iter().map(|n| n + self.smoothing).sum();
+        Some(
+            counts
+                .iter()
+                .map(|n| ((n + self.smoothing) / total) as f32)
+                .collect(),
+        )
+    }
+}
+
+/// Picks a sense uniformly at random.
+///
+/// The pick depends only on the seed, the words of the sentence and the
+/// position of the token, so that runs with the same seed give the same
+/// output whatever the batch size or number of threads.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RandomWSD {
+    seed: u64,
+}
+
+impl RandomWSD {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    pub fn new_as_shared(
+        _saldo: Option<&SaldoLexicon>,
+        argv: &[String],
+    ) -> Result<SharedWSDApplication, UsageError> {
+        let mut seed = 0;
+        for a in argv {
+            if let Some(val) = a.strip_prefix("-seed=") {
+                seed = val.parse().map_err(|_err| UsageError::BadValue {
+                    param: "-seed=".into(),
+                    value: val.to_string(),
+                })?;
+            }
+        }
+        Ok(std::sync::Arc::new(Self::new(seed)))
+    }
+}
+
+impl WSDApplication for RandomWSD {
+    fn disambiguate(&self, lts: &[LemmaToken], i: usize) -> Option<Vec<f32>> {
+        let senses = lts[i].possible_senses();
+        if senses.len() < 2 {
+            return None;
+        }
+        // FNV-1a, which unlike the std hashers is stable between releases
+        let mut hash = 0xcbf29ce484222325u64 ^ self.seed;
+        let words = lts.iter().map(|lt| lt.word().as_bytes());
+        for byte in words
+            .flat_map(|w| w.iter().chain(b"\t"))
+            .chain(&i.to_le_bytes())
+        {
+            hash = (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+        }
+        Some(one_hot(
+            senses.len(),
+            (splitmix64(hash) % senses.len() as u64) as usize,
+        ))
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,172 @@
+use std::{collections::HashMap, io};
+
+use crate::UsageError;
+
+/// How the weight of a context word falls with its distance to the target,
+/// chosen with `-kernel=`.
+///
+/// The weights of a full window sum to 1.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Kernel {
+    /// The same weight for every context word.
+    Uniform,
+    /// Falling linearly from the nearest word to zero just past the edge.
+    Linear,
+    /// `exp(-rate * (distance - 1))`.
+    Exponential { rate: f32 },
+    /// `exp(-(distance - 1)^2 / (2 * sigma^2))`.
+    Gaussian { sigma: f32 },
+    /// `1 / distance`.
+    InverseDistance,
+}
+
+impl Kernel {
+    pub const DEFAULT_RATE: f32 = 0.5;
+    pub const DEFAULT_SIGMA: f32 = 3.0;
+
+    /// The unnormalized weight at `distance` in a window side of `width` words.
+    fn weight(&self, distance: usize, width: usize) -> f32 {
+        let d = distance as f32;
+        match *self {
+            Self::Uniform => 1.0,
+            Self::Linear => 1.0 - d / (width + 1) as f32,
+            Self::Exponential { rate } => (-rate * (d - 1.0)).exp(),
+            Self::Gaussian { sigma } => (-(d - 1.0).powi(2) / (2.0 * sigma * sigma)).exp(),
+            Self::InverseDistance => 1.0 / d,
+        }
+    }
+
+    /// The weights at distance 1, 2, .. on each side of a window of `left`
+    /// and `right` words.
+    pub fn weights(&self, left: usize, right: usize) -> (Vec<f32>, Vec<f32>) {
+        let side =
+            |width: usize| -> Vec<f32> { (1..=width).map(|d| self.weight(d, width)).collect() };
+        let (mut left, mut right) = (side(left), side(right));
+        let total: f32 = left.iter().chain(&right).sum();
+        if total > 0.0 {
+            for w in left.iter_mut().chain(&mut right) {
+                *w /= total;
+            }
+        }
+        (left, right)
+    }
+}
+
+impl std::str::FromStr for Kernel {
+    type Err = UsageError;
+
+    /// Parse `uniform`, `linear`, `exponential[:RATE]`, `gaussian[:SIGMA]` or `inverse`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad_value = || UsageError::BadValue {
+            param: "-kernel=".into(),
+            value: s.to_string(),
+        };
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (s, None),
+        };
+        let param = |default: f32| match arg {
+            Some(p) => p
+                .parse::<f32>()
+                .ok()
+                .filter(|p| p.is_finite() && *p > 0.0)
+                .ok_or_else(bad_value),
+            None => Ok(default),
+        };
+        match name {
+            "uniform" if arg.is_none() => Ok(Self::Uniform),
+            "linear" if arg.is_none() => Ok(Self::Linear),
+            "exponential" => Ok(Self::Exponential {
+                rate: param(Self::DEFAULT_RATE)?,
+            }),
+            "gaussian" => Ok(Self::Gaussian {
+                sigma: param(Self::DEFAULT_SIGMA)?,
+            }),
+            "inverse" if arg.is_none() => Ok(Self::InverseDistance),
+            _ => Err(bad_value()),
+        }
+    }
+}
+
+impl std::fmt::Display for Kernel {
+    /// Write the `-kernel=` value that parses back to this kernel.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Uniform => write!(f, "uniform"),
+            Self::Linear => write!(f, "linear"),
+            Self::Exponential { rate } => write!(f, "exponential:{}", rate),
+            Self::Gaussian { sigma } => write!(f, "gaussian:{}", sigma),
+            Self::InverseDistance => write!(f, "inverse"),
+        }
+    }
+}
+
+impl serde::Serialize for Kernel {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Kernel {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Inverse document frequencies of context lemmas, so that frequent lemmas
+/// weigh less: `ln((1 + total) / (1 + count))`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LemmaIdf {
+    idf: HashMap<String, f32>,
+    /// The weight of lemmas that are not in the counts.
+    unknown: f32,
+}
+
+impl LemmaIdf {
+    pub fn from_counts<S: Into<String>>(counts: impl IntoIterator<Item = (S, u64)>) -> Self {
+        let counts: Vec<(String, u64)> = counts.into_iter().map(|(l, n)| (l.into(), n)).collect();
+        let total = counts.iter().map(|(_, n)| n).sum::<u64>() as f64;
+        let idf = |n: u64| ((1.0 + total) / (1.0 + n as f64)).ln() as f32;
+        Self {
+            unknown: idf(0),
+            idf: counts.into_iter().map(|(l, n)| (l, idf(n))).collect(),
+        }
+    }
+
+    /// Read a frequency file of lines like `utskott..nn 1234`.
+    ///
+    /// Blank lines and lines starting with `#` are skipped, and the counts
+    /// of repeated lemmas are added.
+    pub fn read(reader: &mut dyn io::BufRead) -> io::Result<Self> {
+        let mut counts: HashMap<String, u64> = HashMap::new();
+        for (i, line) in io::BufRead::lines(reader).enumerate() {
+            let line = line?;
+            let text = line.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            let mut fields = text.split_whitespace();
+            match (
+                fields.next(),
+                fields.next().map(str::parse::<u64>),
+                fields.next(),
+            ) {
+                (Some(lemma), Some(Ok(n)), None) => {
+                    *counts.entry(lemma.to_string()).or_default() += n;
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: expected 'LEMMA COUNT', found '{}'", i + 1, line),
+                    ));
+                }
+            }
+        }
+        Ok(Self::from_counts(counts))
+    }
+
+    pub fn get(&self, lemma: &str) -> f32 {
+        self.idf.get(lemma).copied().unwrap_or(self.unknown)
+    }
+}
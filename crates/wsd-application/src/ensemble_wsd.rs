@@ -0,0 +1,393 @@
+use process_corpus::LemmaToken;
+use saldo::SaldoLexicon;
+
+use crate::evaluation::best_sense;
+use crate::wsd_application::disambiguate_document;
+use crate::{
+    DisambiguatedSentence, EvalScores, GoldKey, SharedWSDApplication, TextContext, UsageError,
+    WSDApplication, WSDError,
+};
+
+/// The probability a member's zero probabilities are raised to in a
+/// [`Combination::Product`], so that one member cannot veto a sense.
+const PRODUCT_FLOOR: f32 = 1e-6;
+
+/// The flags every member gets without a prefix.
+const SHARED_FLAGS: [&str; 2] = ["-saldo=", "-saldoVersion="];
+
+/// How an [`EnsembleWSD`] combines the distributions of its members,
+/// chosen with `-combine=`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Combination {
+    /// The weighted mean of the distributions.
+    #[default]
+    Average,
+    /// The weighted geometric mean, renormalized: a product of experts.
+    Product,
+    /// Each member's most probable sense gets the member's weight.
+    Vote,
+}
+
+impl std::str::FromStr for Combination {
+    type Err = UsageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "average" => Ok(Self::Average),
+            "product" => Ok(Self::Product),
+            "vote" => Ok(Self::Vote),
+            _ => Err(UsageError::BadValue {
+                param: "-combine=".into(),
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+pub struct EnsembleMember {
+    /// The `-appName=` of the member.
+    pub name: String,
+    /// The prefix of the member's flags, like `-ALIAS.contextWidth=`, which
+    /// is the name unless given.
+    pub alias: String,
+    pub weight: f32,
+    pub wsd: SharedWSDApplication,
+}
+
+/// Combines the sense distributions of several applications.
+///
+/// Members that give no distribution for a token are left out of its
+/// combination, and the token gets none only if no member gives one.
+pub struct EnsembleWSD {
+    members: Vec<EnsembleMember>,
+    combination: Combination,
+}
+
+impl EnsembleWSD {
+    pub fn new(members: Vec<EnsembleMember>, combination: Combination) -> Self {
+        Self {
+            members,
+            combination,
+        }
+    }
+
+    pub fn new_as_shared(
+        saldo: Option<&SaldoLexicon>,
+        argv: &[String],
+    ) -> Result<SharedWSDApplication, UsageError> {
+        Ok(std::sync::Arc::new(Self::from_args(saldo, argv)?))
+    }
+
+    /// Create from `-members=NAME[:ALIAS][:WEIGHT],...` and `-combine=`.
+    ///
+    /// Each member is created with the flags prefixed with its alias, like
+    /// `-PageRankWSD.contextWidth=5`, without the prefix, and the shared flags
+    /// such as `-saldo=`. A second field that is a number is the weight, so
+    /// `VectorWSD:narrow` is an alias and `VectorWSD:0.5` a weight.
+    pub fn from_args(saldo: Option<&SaldoLexicon>, argv: &[String]) -> Result<Self, UsageError> {
+        let mut spec = None;
+        let mut combination = Combination::default();
+        for a in argv {
+            if let Some(val) = a.strip_prefix("-members=") {
+                spec = Some(val);
+            } else if let Some(val) = a.strip_prefix("-combine=") {
+                combination = val.parse()?;
+            }
+        }
+        let spec = spec.ok_or_else(|| UsageError::missing_required_argument("-members="))?;
+        let bad_value = || UsageError::BadValue {
+            param: "-members=".into(),
+            value: spec.to_string(),
+        };
+        let parse_weight = |weight: &str| {
+            weight
+                .parse()
+                .ok()
+                .filter(|w: &f32| w.is_finite() && *w >= 0.0)
+                .ok_or_else(bad_value)
+        };
+        let mut members: Vec<EnsembleMember> = Vec::new();
+        for member in spec.split(',') {
+            let fields: Vec<&str> = member.split(':').collect();
+            let (name, alias, weight) = match fields[..] {
+                [name] => (name, name, 1.0),
+                [name, weight] if weight.parse::<f32>().is_ok() => {
+                    (name, name, parse_weight(weight)?)
+                }
+                [name, alias] => (name, alias, 1.0),
+                [name, alias, weight] => (name, alias, parse_weight(weight)?),
+                _ => return Err(bad_value()),
+            };
+            // a nested ensemble would read the same -members=, and members
+            // with the same alias would get each other's flags
+            if name.is_empty()
+                || alias.is_empty()
+                || name == "EnsembleWSD"
+                || members.iter().any(|m| m.alias == alias)
+            {
+                return Err(bad_value());
+            }
+            let prefix = format!("-{}.", alias);
+            let member_argv: Vec<String> = argv
+                .iter()
+                .filter(|a| SHARED_FLAGS.iter().any(|flag| a.starts_with(flag)))
+                .cloned()
+                .chain(
+                    argv.iter()
+                        .filter_map(|a| a.strip_prefix(&prefix).map(|a| format!("-{}", a))),
+                )
+                .collect();
+            let wsd =
+                crate::make_wsd_application(saldo, name, &member_argv).map_err(
+                    |err| match err {
+                        WSDError::UsageError { source, .. } => source,
+                        WSDError::UnknownWSDName(_) => bad_value(),
+                    },
+                )?;
+            members.push(EnsembleMember {
+                name: name.to_string(),
+                alias: alias.to_string(),
+                weight,
+                wsd,
+            });
+        }
+        Ok(Self::new(members, combination))
+    }
+
+    pub fn members(&self) -> &[EnsembleMember] {
+        &self.members
+    }
+
+    /// The members and weights in the form of `-members=`.
+    pub fn members_spec(&self) -> String {
+        self.members
+            .iter()
+            .map(|m| {
+                if m.alias == m.name {
+                    format!("{}:{}", m.name, m.weight)
+                } else {
+                    format!("{}:{}:{}", m.name, m.alias, m.weight)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn set_weights(&mut self, weights: &[f32]) {
+        for (member, &weight) in self.members.iter_mut().zip(weights) {
+            member.weight = weight;
+        }
+    }
+
+    /// Combine the distributions the members gave for a token with `len` senses.
+    fn combine(&self, dists: &[Option<Vec<f32>>], weights: &[f32], len: usize) -> Option<Vec<f32>> {
+        let answers: Vec<(&[f32], f32)> = dists
+            .iter()
+            .zip(weights)
+            .filter_map(|(dist, &w)| Some((dist.as_deref()?, w)))
+            .filter(|(dist, w)| dist.len() == len && *w > 0.0)
+            .collect();
+        let total: f32 = answers.iter().map(|(_, w)| w).sum();
+        if answers.is_empty() || len == 0 {
+            return None;
+        }
+        let mut out = vec![0f32; len];
+        match self.combination {
+            Combination::Average => {
+                for (dist, w) in &answers {
+                    for (o, p) in out.iter_mut().zip(*dist) {
+                        *o += w * p / total;
+                    }
+                }
+            }
+            Combination::Product => {
+                for (dist, w) in &answers {
+                    for (o, p) in out.iter_mut().zip(*dist) {
+                        *o += w * p.max(PRODUCT_FLOOR).ln() / total;
+                    }
+                }
+                let max = out.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                for o in &mut out {
+                    *o = (*o - max).exp();
+                }
+                let sum: f32 = out.iter().sum();
+                for o in &mut out {
+                    *o /= sum;
+                }
+            }
+            Combination::Vote => {
+                for (dist, w) in &answers {
+                    let best = dist
+                        .iter()
+                        .enumerate()
+                        .fold(0, |best, (i, p)| if *p > dist[best] { i } else { best });
+                    out[best] += w / total;
+                }
+            }
+        }
+        Some(out)
+    }
+
+    fn weights(&self) -> Vec<f32> {
+        self.members.iter().map(|m| m.weight).collect()
+    }
+
+    /// Combine the sentence-level outputs of the members.
+    fn combine_sentence(
+        &self,
+        lts: Vec<LemmaToken>,
+        outputs: Vec<Vec<Option<Vec<f32>>>>,
+    ) -> DisambiguatedSentence {
+        let weights = self.weights();
+        let disamb = (0..lts.len())
+            .map(|i| {
+                let dists: Vec<Option<Vec<f32>>> = outputs
+                    .iter()
+                    .map(|out| out.get(i).cloned().flatten())
+                    .collect();
+                self.combine(&dists, &weights, lts[i].possible_senses().len())
+            })
+            .collect();
+        (lts, disamb)
+    }
+
+    /// Choose the weights that get the most instances of `key` right in
+    /// `documents`, searching all weights that are multiples of `step` and
+    /// sum to 1, and keep them. Returns the scores with those weights.
+    ///
+    /// The members disambiguate `documents` once, in context as
+    /// [`disambiguate_sentences`](crate::wsd_application::disambiguate_sentences)
+    /// does; only the combination is repeated.
+    pub fn tune(
+        &mut self,
+        documents: &[Vec<Vec<LemmaToken>>],
+        key: &GoldKey,
+        step: f32,
+    ) -> EvalScores {
+        let outputs: Vec<Vec<DisambiguatedSentence>> = self
+            .members
+            .iter()
+            .map(|m| {
+                documents
+                    .iter()
+                    .flat_map(|document| disambiguate_document(m.wsd.as_ref(), document.clone()))
+                    .collect()
+            })
+            .collect();
+        let mut instances = Vec::new();
+        for (s, lts) in documents.iter().flatten().enumerate() {
+            for (t, lt) in lts.iter().enumerate() {
+                let Some(gold) = key.get(s + 1, t + 1) else {
+                    continue;
+                };
+                let dists: Vec<Option<Vec<f32>>> = outputs
+                    .iter()
+                    .map(|out| out[s].1.get(t).cloned().flatten())
+                    .collect();
+                instances.push((lt, dists, gold));
+            }
+        }
+
+        let num_steps = (1.0 / step.clamp(f32::EPSILON, 1.0)).round().max(1.0) as usize;
+        let mut best: Option<(Vec<f32>, EvalScores)> = None;
+        for parts in compositions(num_steps, self.members.len()) {
+            let weights: Vec<f32> = parts.iter().map(|&n| n as f32 / num_steps as f32).collect();
+            let mut scores = EvalScores {
+                instances: key.len(),
+                ..EvalScores::default()
+            };
+            for (lt, dists, gold) in &instances {
+                let combined = self.combine(dists, &weights, lt.possible_senses().len());
+                if let Some(predicted) = combined.as_deref().and_then(|p| best_sense(lt, p)) {
+                    scores.attempted += 1;
+                    scores.correct += usize::from(gold.iter().any(|g| g == predicted));
+                }
+            }
+            if best
+                .as_ref()
+                .is_none_or(|(_, b)| (scores.correct, scores.attempted) > (b.correct, b.attempted))
+            {
+                best = Some((weights, scores));
+            }
+        }
+        let (weights, scores) = best.unwrap_or_default();
+        self.set_weights(&weights);
+        scores
+    }
+}
+
+/// All ways to write `total` as an ordered sum of `len` natural numbers.
+fn compositions(total: usize, len: usize) -> Vec<Vec<usize>> {
+    match len {
+        0 => Vec::new(),
+        1 => vec![vec![total]],
+        _ => (0..=total)
+            .rev()
+            .flat_map(|first| {
+                compositions(total - first, len - 1)
+                    .into_iter()
+                    .map(move |mut rest| {
+                        rest.insert(0, first);
+                        rest
+                    })
+            })
+            .collect(),
+    }
+}
+
+impl WSDApplication for EnsembleWSD {
+    fn disambiguate_sentence(&self, lts: Vec<LemmaToken>) -> DisambiguatedSentence {
+        let outputs = self
+            .members
+            .iter()
+            .map(|m| m.wsd.disambiguate_sentence(lts.clone()).1)
+            .collect();
+        self.combine_sentence(lts, outputs)
+    }
+
+    fn disambiguate(&self, lts: &[LemmaToken], i: usize) -> Option<Vec<f32>> {
+        let dists: Vec<Option<Vec<f32>>> = self
+            .members
+            .iter()
+            .map(|m| m.wsd.disambiguate(lts, i))
+            .collect();
+        self.combine(&dists, &self.weights(), lts[i].possible_senses().len())
+    }
+
+    fn context_sentences(&self) -> usize {
+        self.members
+            .iter()
+            .map(|m| m.wsd.context_sentences())
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn uses_document(&self) -> bool {
+        self.members.iter().any(|m| m.wsd.uses_document())
+    }
+
+    /// Each member gets as much of the context as it asks for.
+    fn disambiguate_in_context(
+        &self,
+        lts: Vec<LemmaToken>,
+        context: &TextContext<'_>,
+    ) -> DisambiguatedSentence {
+        let outputs = self
+            .members
+            .iter()
+            .map(|m| {
+                let n = m.wsd.context_sentences();
+                let member_context = TextContext {
+                    before: &context.before[context.before.len().saturating_sub(n)..],
+                    after: &context.after[..n.min(context.after.len())],
+                    document: context.document.filter(|_| m.wsd.uses_document()),
+                    ..*context
+                };
+                m.wsd
+                    .disambiguate_in_context(lts.clone(), &member_context)
+                    .1
+            })
+            .collect();
+        self.combine_sentence(lts, outputs)
+    }
+}
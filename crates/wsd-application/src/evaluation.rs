@@ -0,0 +1,276 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, io,
+};
+
+use process_corpus::{CorpusError, LemmaToken, ParseError, ParseErrorKind};
+use serde::ser::SerializeStruct;
+
+use crate::wsd_application::{DisambiguateOptions, disambiguate_documents};
+use crate::{DisambiguatedSentence, SharedWSDApplication, SourceFormat};
+
+/// The gold senses of the instances to evaluate.
+///
+/// Each line holds an instance id, `SENTENCE.TOKEN` with both numbers
+/// 1-based, and one or more correct senses, separated by whitespace:
+///
+/// ```text
+/// 1.17 utskott..2
+/// 1.22 anslag..1 anslag..2
+/// ```
+///
+/// Blank lines and lines starting with `#` are skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoldKey {
+    senses: BTreeMap<(usize, usize), Vec<String>>,
+}
+
+impl GoldKey {
+    pub fn read(reader: &mut dyn io::BufRead) -> Result<Self, CorpusError> {
+        let mut senses = BTreeMap::new();
+        for (i, line) in io::BufRead::lines(reader).enumerate() {
+            let line = line?;
+            let text = line.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| {
+                ParseError::new(i + 1, 1, &line, ParseErrorKind::Other(message.into()))
+            };
+            let mut fields = text.split_whitespace();
+            let id = fields.next().unwrap_or_default();
+            let instance = id
+                .split_once('.')
+                .and_then(|(s, t)| Some((s.parse().ok()?, t.parse().ok()?)))
+                .filter(|&(s, t)| s > 0 && t > 0)
+                .ok_or_else(|| error("expected an instance id like '1.17'"))?;
+            let gold: Vec<String> = fields.map(String::from).collect();
+            if gold.is_empty() {
+                return Err(error("no gold sense").into());
+            }
+            if senses.insert(instance, gold).is_some() {
+                return Err(error("duplicate instance id").into());
+            }
+        }
+        Ok(Self { senses })
+    }
+
+    /// The number of instances.
+    pub fn len(&self) -> usize {
+        self.senses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.senses.is_empty()
+    }
+
+    pub fn get(&self, sentence: usize, token: usize) -> Option<&[String]> {
+        self.senses.get(&(sentence, token)).map(Vec::as_slice)
+    }
+}
+
+/// Counts of instances, with the scores computed from them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EvalScores {
+    pub instances: usize,
+    /// Instances the application gave a sense distribution for.
+    pub attempted: usize,
+    /// Attempted instances whose most probable sense is a gold sense.
+    pub correct: usize,
+}
+
+impl EvalScores {
+    fn add(&mut self, attempted: bool, correct: bool) {
+        self.instances += 1;
+        self.attempted += usize::from(attempted);
+        self.correct += usize::from(correct);
+    }
+
+    /// Correct instances among all instances, counting unattempted ones as wrong.
+    pub fn accuracy(&self) -> f64 {
+        ratio(self.correct, self.instances)
+    }
+
+    pub fn coverage(&self) -> f64 {
+        ratio(self.attempted, self.instances)
+    }
+
+    pub fn precision(&self) -> f64 {
+        ratio(self.correct, self.attempted)
+    }
+
+    pub fn recall(&self) -> f64 {
+        ratio(self.correct, self.instances)
+    }
+
+    pub fn f1(&self) -> f64 {
+        let (p, r) = (self.precision(), self.recall());
+        if p + r == 0.0 {
+            0.0
+        } else {
+            2.0 * p * r / (p + r)
+        }
+    }
+}
+
+fn ratio(n: usize, d: usize) -> f64 {
+    if d == 0 { 0.0 } else { n as f64 / d as f64 }
+}
+
+impl serde::Serialize for EvalScores {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("EvalScores", 8)?;
+        s.serialize_field("instances", &self.instances)?;
+        s.serialize_field("attempted", &self.attempted)?;
+        s.serialize_field("correct", &self.correct)?;
+        s.serialize_field("accuracy", &self.accuracy())?;
+        s.serialize_field("coverage", &self.coverage())?;
+        s.serialize_field("precision", &self.precision())?;
+        s.serialize_field("recall", &self.recall())?;
+        s.serialize_field("f1", &self.f1())?;
+        s.end()
+    }
+}
+
+/// The scores over all instances and per lemma and part of speech.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct EvalReport {
+    pub total: EvalScores,
+    /// The instances that are missing from the input or whose token has no
+    /// lemma, which are in neither `per_lemma` nor `per_pos`.
+    pub no_lemma: EvalScores,
+    /// Keyed by the instance's first lemma, like `anslag..nn`.
+    pub per_lemma: BTreeMap<String, EvalScores>,
+    /// Keyed by the part of speech of that lemma, like `nn`.
+    pub per_pos: BTreeMap<String, EvalScores>,
+}
+
+impl EvalReport {
+    /// Write the report as pretty-printed JSON.
+    pub fn write_json(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, self)?;
+        writeln!(out)
+    }
+
+    fn add(&mut self, lemma: Option<&str>, attempted: bool, correct: bool) {
+        self.total.add(attempted, correct);
+        let Some(lemma) = lemma else {
+            self.no_lemma.add(attempted, correct);
+            return;
+        };
+        let pos = lemma.rsplit_once("..").map(|(_, pos)| pos).unwrap_or("_");
+        for (map, key) in [(&mut self.per_lemma, lemma), (&mut self.per_pos, pos)] {
+            map.entry(key.to_string())
+                .or_default()
+                .add(attempted, correct);
+        }
+    }
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .per_lemma
+            .keys()
+            .chain(self.per_pos.keys())
+            .map(|k| k.chars().count())
+            .chain(["no lemma".len()])
+            .max()
+            .unwrap_or_default();
+        let row = |f: &mut fmt::Formatter<'_>, name: &str, s: &EvalScores| {
+            writeln!(
+                f,
+                "{:<width$}  {:>9}  {:>9}  {:>7}  {:>8.3}  {:>8.3}  {:>9.3}  {:>6.3}  {:>5.3}",
+                name,
+                s.instances,
+                s.attempted,
+                s.correct,
+                s.accuracy(),
+                s.coverage(),
+                s.precision(),
+                s.recall(),
+                s.f1(),
+            )
+        };
+        let header = |f: &mut fmt::Formatter<'_>, name: &str| {
+            writeln!(
+                f,
+                "{:<width$}  instances  attempted  correct  accuracy  coverage  precision  recall     f1",
+                name
+            )
+        };
+        header(f, "")?;
+        row(f, "total", &self.total)?;
+        row(f, "no lemma", &self.no_lemma)?;
+        for (name, scores) in [("pos", &self.per_pos), ("lemma", &self.per_lemma)] {
+            writeln!(f)?;
+            header(f, name)?;
+            for (key, s) in scores {
+                row(f, key, s)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Disambiguate the sentences `f` reads, with the context the application
+/// asks for, and score the instances in `key`.
+///
+/// Instances that are missing from the input count as unattempted.
+pub fn evaluate(
+    wsd: SharedWSDApplication,
+    reader: &mut dyn io::BufRead,
+    f: &mut dyn SourceFormat,
+    key: &GoldKey,
+    opts: DisambiguateOptions,
+) -> Result<EvalReport, CorpusError> {
+    let mut report = EvalReport::default();
+    let mut seen = BTreeSet::new();
+    let mut num_sentences = 0;
+    let mut score = |f: &mut dyn SourceFormat, (lts, scores): DisambiguatedSentence| {
+        num_sentences += 1;
+        f.output().skip_sentence();
+        for (i, (lt, scores)) in lts.iter().zip(&scores).enumerate() {
+            let Some(gold) = key.get(num_sentences, i + 1) else {
+                continue;
+            };
+            seen.insert((num_sentences, i + 1));
+            let predicted = scores.as_deref().and_then(|scores| best_sense(lt, scores));
+            report.add(
+                lt.possible_lemmas().first().map(String::as_str),
+                predicted.is_some(),
+                predicted.is_some_and(|p| gold.iter().any(|g| g == p)),
+            );
+        }
+        Ok(())
+    };
+    disambiguate_documents(wsd.as_ref(), reader, f, opts, &|_| true, &mut score)?;
+    if seen.len() < key.len() {
+        log::warn!(
+            "{} of {} instances are not in the input",
+            key.len() - seen.len(),
+            key.len()
+        );
+        for instance in key.senses.keys() {
+            if !seen.contains(instance) {
+                report.add(None, false, false);
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// The most probable sense, or `None` if there are no scores.
+pub(crate) fn best_sense<'a>(lt: &'a LemmaToken, scores: &[f32]) -> Option<&'a str> {
+    lt.possible_senses()
+        .iter()
+        .zip(scores)
+        .fold(
+            None,
+            |best: Option<(&String, f32)>, (sense, &p)| match best {
+                Some((_, q)) if q >= p => best,
+                _ => Some((sense, p)),
+            },
+        )
+        .map(|(sense, _)| sense.as_str())
+}
@@ -2,21 +2,56 @@ use std::io;
 
 use saldo::SaldoLexicon;
 
+mod baselines;
+mod context_weights;
+mod ensemble_wsd;
+mod evaluation;
+mod model_bundle;
+mod model_check;
+mod output_format;
+mod page_rank_wsd;
+mod sense_backoff;
+mod sense_ratios;
 mod source_format;
 mod vector_wsd;
 pub mod wsd_application;
 
-pub use self::source_format::{SourceFormat, TabFormat};
-use vector_wsd::VectorWSD;
-pub use wsd_application::{SharedWSDApplication, WSDApplication};
+pub use self::baselines::{FirstSenseWSD, MostFrequentSenseWSD, RandomWSD, SenseFrequencies};
+pub use self::context_weights::{Kernel, LemmaIdf};
+pub use self::ensemble_wsd::{Combination, EnsembleMember, EnsembleWSD};
+pub use self::evaluation::{EvalReport, EvalScores, GoldKey, evaluate};
+pub use self::model_bundle::{
+    BUNDLE_FORMAT_VERSION, BundleFile, BundleHyperparameters, BundleManifest, MANIFEST_FILE,
+    ModelBundle,
+};
+pub use self::model_check::{AmbiguousLemgram, ModelReport, SaldoCoverage};
+pub use self::output_format::{
+    ConlluOutput, JsonOutput, OutputFormat, SbXmlOutput, SparvOutput, TabOutput, VrtOutput,
+};
+pub use self::page_rank_wsd::{PageRankMode, PageRankOptions, PageRankWSD};
+pub use self::sense_backoff::SenseBackoff;
+pub use self::sense_ratios::{LemmaRatios, SenseRatios, sense_ratios};
+pub use self::source_format::{
+    ConlluFormat, DEFAULT_SENSE_KEY, JsonlFormat, SbXmlFormat, Sentences, SourceFormat, TabColumn,
+    TabColumns, TabFormat, VrtAttributes, VrtFormat,
+};
+pub use vector_wsd::{SharedEmbeddings, VectorStore, VectorWSD, VectorWSDOptions};
+pub use wsd_application::{
+    DisambiguatedSentence, DocumentBag, SharedWSDApplication, TextContext, WSDApplication,
+};
 
 pub fn make_wsd_application(
-    _saldo: Option<&SaldoLexicon>,
+    saldo: Option<&SaldoLexicon>,
     cls_name: &str,
     argv: &[String],
 ) -> Result<SharedWSDApplication, WSDError> {
     let res = match cls_name {
-        "se.gu.spraakbanken.wsd.VectorWSD" | "VectorWSD" => VectorWSD::new_as_shared(argv),
+        "se.gu.spraakbanken.wsd.VectorWSD" | "VectorWSD" => VectorWSD::new_as_shared(saldo, argv),
+        "PageRankWSD" => PageRankWSD::new_as_shared(saldo, argv),
+        "FirstSenseWSD" => FirstSenseWSD::new_as_shared(saldo, argv),
+        "MostFrequentSenseWSD" => MostFrequentSenseWSD::new_as_shared(saldo, argv),
+        "RandomWSD" => RandomWSD::new_as_shared(saldo, argv),
+        "EnsembleWSD" => EnsembleWSD::new_as_shared(saldo, argv),
         _ => return Err(WSDError::UnknownWSDName(cls_name.to_string())),
     };
     res.map_err(|error| WSDError::UsageError {
@@ -48,6 +83,38 @@ pub enum UsageError {
         path: String,
         source: io::Error,
     },
+    #[error(
+        "Sense vectors (-svFile=) have dimension {sense_dim}, but context vectors (-cvFile=) have dimension {context_dim}"
+    )]
+    DimensionMismatch {
+        sense_dim: usize,
+        context_dim: usize,
+    },
+    #[error("None of the {num_sense_vectors} sense ids in -svFile= is known to the SALDO lexicon")]
+    NoKnownSenses { num_sense_vectors: usize },
+    #[error("Invalid model bundle manifest '{path}'")]
+    InvalidManifest {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[error(
+        "Model bundle '{path}' has format version {version}, which is newer than this program supports"
+    )]
+    UnsupportedBundleVersion { path: String, version: u32 },
+    #[error(
+        "Model bundle '{path}' was made for SALDO version {expected}, but -saldoVersion= is {actual}"
+    )]
+    SaldoVersionMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Checksum mismatch for '{path}': expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
     #[error("Word2Vec error for flag '{param}' when reading from '{path}'")]
     Word2VecError {
         param: String,
@@ -0,0 +1,275 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{Kernel, UsageError, VectorWSDOptions};
+
+/// The newest bundle format this version can read.
+///
+/// Version 2 added the context weighting and the IDF file.
+pub const BUNDLE_FORMAT_VERSION: u32 = 2;
+
+/// Name of the manifest inside a bundle directory.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The manifest of a model bundle, a directory holding a sense model, a
+/// context model and this description of how they were made.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub sense_model: BundleFile,
+    pub context_model: BundleFile,
+    pub hyperparameters: BundleHyperparameters,
+    /// The lemma frequencies given as `-idfFile=`, if any.
+    #[serde(default)]
+    pub idf: Option<BundleFile>,
+    /// The SALDO version the sense ids come from.
+    #[serde(default)]
+    pub saldo_version: Option<String>,
+    /// A free-text description of the corpus the models were trained on.
+    #[serde(default)]
+    pub training_corpus: Option<String>,
+}
+
+/// A file in a bundle, with its path relative to the bundle directory.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BundleFile {
+    pub path: String,
+    /// Hex-encoded SHA-256 of the file.
+    pub sha256: String,
+}
+
+/// The `VectorWSD` flags the models were trained for.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BundleHyperparameters {
+    pub decay: bool,
+    pub s1prior: f32,
+    pub context_width: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel: Option<Kernel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub left_width: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right_width: Option<usize>,
+    #[serde(default)]
+    pub context_sentences: usize,
+    #[serde(default)]
+    pub document_weight: f32,
+}
+
+/// A bundle directory with a parsed manifest.
+#[derive(Debug, Clone)]
+pub struct ModelBundle {
+    dir: PathBuf,
+    manifest: BundleManifest,
+}
+
+impl From<BundleHyperparameters> for VectorWSDOptions {
+    fn from(
+        BundleHyperparameters {
+            decay,
+            s1prior,
+            context_width,
+            kernel,
+            left_width,
+            right_width,
+            context_sentences,
+            document_weight,
+        }: BundleHyperparameters,
+    ) -> Self {
+        Self {
+            decay,
+            s1prior,
+            context_width,
+            kernel,
+            left_width,
+            right_width,
+            context_sentences,
+            document_weight,
+        }
+    }
+}
+
+impl From<VectorWSDOptions> for BundleHyperparameters {
+    fn from(
+        VectorWSDOptions {
+            decay,
+            s1prior,
+            context_width,
+            kernel,
+            left_width,
+            right_width,
+            context_sentences,
+            document_weight,
+        }: VectorWSDOptions,
+    ) -> Self {
+        Self {
+            decay,
+            s1prior,
+            context_width,
+            kernel,
+            left_width,
+            right_width,
+            context_sentences,
+            document_weight,
+        }
+    }
+}
+
+impl ModelBundle {
+    /// Read the manifest of the bundle in `dir`.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, UsageError> {
+        let dir = dir.as_ref().to_path_buf();
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let content = fs::read_to_string(&manifest_path).map_err(|source| UsageError::IoError {
+            param: "-model=".into(),
+            path: manifest_path.display().to_string(),
+            source,
+        })?;
+        let manifest: BundleManifest =
+            serde_json::from_str(&content).map_err(|source| UsageError::InvalidManifest {
+                path: manifest_path.display().to_string(),
+                source,
+            })?;
+        if manifest.format_version > BUNDLE_FORMAT_VERSION {
+            return Err(UsageError::UnsupportedBundleVersion {
+                path: manifest_path.display().to_string(),
+                version: manifest.format_version,
+            });
+        }
+        log::info!(
+            "Model bundle '{}': SALDO version {}, trained on {}",
+            dir.display(),
+            manifest.saldo_version.as_deref().unwrap_or("unknown"),
+            manifest
+                .training_corpus
+                .as_deref()
+                .unwrap_or("unknown corpus")
+        );
+        Ok(Self { dir, manifest })
+    }
+
+    /// Create a bundle in `dir` by copying the two models, and the lemma
+    /// frequencies if given, into it and writing a manifest.
+    pub fn create(
+        dir: impl AsRef<Path>,
+        sense_model: &str,
+        context_model: &str,
+        idf_file: Option<&str>,
+        hyperparameters: BundleHyperparameters,
+        saldo_version: Option<String>,
+        training_corpus: Option<String>,
+    ) -> Result<Self, UsageError> {
+        let dir = dir.as_ref().to_path_buf();
+        let to_usage_error = |path: &Path| {
+            let path = path.display().to_string();
+            move |source| UsageError::IoError {
+                param: "-createBundle=".into(),
+                path,
+                source,
+            }
+        };
+        fs::create_dir_all(&dir).map_err(to_usage_error(&dir))?;
+        let copy_file = |src: &str, name: &str| -> Result<BundleFile, UsageError> {
+            let dst = dir.join(name);
+            fs::copy(src, &dst).map_err(to_usage_error(Path::new(src)))?;
+            Ok(BundleFile {
+                path: name.to_string(),
+                sha256: sha256_file(&dst).map_err(to_usage_error(&dst))?,
+            })
+        };
+        let manifest = BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            sense_model: copy_file(sense_model, "sense.bin")?,
+            context_model: copy_file(context_model, "context.bin")?,
+            hyperparameters,
+            idf: idf_file
+                .map(|path| copy_file(path, "idf.txt"))
+                .transpose()?,
+            saldo_version,
+            training_corpus,
+        };
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let content = serde_json::to_string_pretty(&manifest).expect("manifest is serializable");
+        fs::write(&manifest_path, content + "\n").map_err(to_usage_error(&manifest_path))?;
+        Ok(Self { dir, manifest })
+    }
+
+    pub fn manifest(&self) -> &BundleManifest {
+        &self.manifest
+    }
+
+    pub fn sense_model_path(&self) -> PathBuf {
+        self.dir.join(&self.manifest.sense_model.path)
+    }
+
+    pub fn context_model_path(&self) -> PathBuf {
+        self.dir.join(&self.manifest.context_model.path)
+    }
+
+    pub fn idf_path(&self) -> Option<PathBuf> {
+        self.manifest
+            .idf
+            .as_ref()
+            .map(|file| self.dir.join(&file.path))
+    }
+
+    /// Check that the lexicon in use, of version `saldo_version` if known, is
+    /// the one the models were made for. Without a version, this can only be
+    /// warned about.
+    pub fn check_saldo_version(&self, saldo_version: Option<&str>) -> Result<(), UsageError> {
+        match (self.manifest.saldo_version.as_deref(), saldo_version) {
+            (Some(expected), Some(actual)) if expected != actual => {
+                Err(UsageError::SaldoVersionMismatch {
+                    path: self.dir.display().to_string(),
+                    expected: expected.to_string(),
+                    actual: actual.to_string(),
+                })
+            }
+            (Some(expected), None) => {
+                log::warn!(
+                    "Model bundle '{}' was made for SALDO version {}; give -saldoVersion= to check the lexicon",
+                    self.dir.display(),
+                    expected
+                );
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the files still have the checksums recorded in the manifest.
+    pub fn verify_checksums(&self) -> Result<(), UsageError> {
+        let files = [&self.manifest.sense_model, &self.manifest.context_model];
+        for file in files.into_iter().chain(&self.manifest.idf) {
+            let path = self.dir.join(&file.path);
+            log::debug!("Verifying checksum of '{}' ...", path.display());
+            let actual = sha256_file(&path).map_err(|source| UsageError::IoError {
+                param: "-model=".into(),
+                path: path.display().to_string(),
+                source,
+            })?;
+            if !actual.eq_ignore_ascii_case(&file.sha256) {
+                return Err(UsageError::ChecksumMismatch {
+                    path: path.display().to_string(),
+                    expected: file.sha256.clone(),
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+
+use saldo::{PRIM, SaldoLexicon};
+use w2v::Embeddings;
+
+use crate::UsageError;
+
+/// How well a pair of sense and context models fit each other and SALDO.
+#[derive(Debug, Clone)]
+pub struct ModelReport {
+    pub sense_dim: usize,
+    pub context_dim: usize,
+    pub num_sense_vectors: usize,
+    pub num_context_vectors: usize,
+    pub saldo: Option<SaldoCoverage>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SaldoCoverage {
+    /// Number of SALDO senses, not counting `PRIM..1`.
+    pub num_saldo_senses: usize,
+    pub num_saldo_senses_with_vectors: usize,
+    /// Sense ids in the sense model that SALDO does not know, sorted.
+    pub unknown_sense_ids: Vec<String>,
+    /// Lemgrams with several senses where at least one sense has no vector, sorted.
+    pub ambiguous_lemgrams_missing_vectors: Vec<AmbiguousLemgram>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmbiguousLemgram {
+    pub lemgram: String,
+    pub num_senses: usize,
+    pub senses_without_vectors: Vec<String>,
+}
+
+impl ModelReport {
+    pub fn new(
+        sense_vectors: &dyn Embeddings,
+        context_vectors: &dyn Embeddings,
+        saldo: Option<&SaldoLexicon>,
+    ) -> Self {
+        Self {
+            sense_dim: sense_vectors.dim(),
+            context_dim: context_vectors.dim(),
+            num_sense_vectors: sense_vectors.len(),
+            num_context_vectors: context_vectors.len(),
+            saldo: saldo.map(|saldo| SaldoCoverage::new(sense_vectors, saldo)),
+        }
+    }
+
+    /// Fail if the models cannot be used together.
+    ///
+    /// The sense and context vectors must have the same dimension, and when a
+    /// SALDO lexicon is loaded at least one sense id must be known to it.
+    pub fn validate(&self) -> Result<(), UsageError> {
+        if self.sense_dim != self.context_dim {
+            return Err(UsageError::DimensionMismatch {
+                sense_dim: self.sense_dim,
+                context_dim: self.context_dim,
+            });
+        }
+        if let Some(saldo) = &self.saldo {
+            if self.num_sense_vectors > 0 && saldo.unknown_sense_ids.len() == self.num_sense_vectors
+            {
+                return Err(UsageError::NoKnownSenses {
+                    num_sense_vectors: self.num_sense_vectors,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SaldoCoverage {
+    fn new(sense_vectors: &dyn Embeddings, saldo: &SaldoLexicon) -> Self {
+        let mut unknown_sense_ids: Vec<String> = sense_vectors
+            .words()
+            .filter(|id| !saldo.contains(id))
+            .map(str::to_string)
+            .collect();
+        unknown_sense_ids.sort();
+
+        let mut num_saldo_senses = 0;
+        let mut num_saldo_senses_with_vectors = 0;
+        for entry in saldo.entries() {
+            if entry.get_id().as_str() == PRIM {
+                continue;
+            }
+            num_saldo_senses += 1;
+            if sense_vectors.contains(entry.get_id().as_str()) {
+                num_saldo_senses_with_vectors += 1;
+            }
+        }
+
+        let mut ambiguous_lemgrams_missing_vectors: Vec<AmbiguousLemgram> = saldo
+            .lemgrams()
+            .filter(|lemgram| lemgram.entries().len() > 1)
+            .filter_map(|lemgram| {
+                let mut senses_without_vectors: Vec<String> = lemgram
+                    .entries()
+                    .iter()
+                    .filter(|id| !sense_vectors.contains(id.as_str()))
+                    .map(ToString::to_string)
+                    .collect();
+                if senses_without_vectors.is_empty() {
+                    return None;
+                }
+                senses_without_vectors.sort();
+                Some(AmbiguousLemgram {
+                    lemgram: lemgram.id().to_string(),
+                    num_senses: lemgram.entries().len(),
+                    senses_without_vectors,
+                })
+            })
+            .collect();
+        ambiguous_lemgrams_missing_vectors.sort_by(|a, b| a.lemgram.cmp(&b.lemgram));
+
+        Self {
+            num_saldo_senses,
+            num_saldo_senses_with_vectors,
+            unknown_sense_ids,
+            ambiguous_lemgrams_missing_vectors,
+        }
+    }
+}
+
+impl fmt::Display for ModelReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "sense vectors:\t{}\tdim={}",
+            self.num_sense_vectors, self.sense_dim
+        )?;
+        writeln!(
+            f,
+            "context vectors:\t{}\tdim={}",
+            self.num_context_vectors, self.context_dim
+        )?;
+        if self.sense_dim != self.context_dim {
+            writeln!(f, "ERROR: dimensions differ")?;
+        }
+        let Some(saldo) = &self.saldo else {
+            return writeln!(f, "no SALDO lexicon loaded (-saldo=)");
+        };
+        writeln!(
+            f,
+            "SALDO senses with vectors:\t{}/{}",
+            saldo.num_saldo_senses_with_vectors, saldo.num_saldo_senses
+        )?;
+        writeln!(
+            f,
+            "sense ids unknown to SALDO:\t{}",
+            saldo.unknown_sense_ids.len()
+        )?;
+        for id in &saldo.unknown_sense_ids {
+            writeln!(f, "\t{}", id)?;
+        }
+        writeln!(
+            f,
+            "ambiguous lemgrams with senses lacking vectors:\t{}",
+            saldo.ambiguous_lemgrams_missing_vectors.len()
+        )?;
+        for lemgram in &saldo.ambiguous_lemgrams_missing_vectors {
+            writeln!(
+                f,
+                "\t{}\t{}/{}\t{}",
+                lemgram.lemgram,
+                lemgram.senses_without_vectors.len(),
+                lemgram.num_senses,
+                lemgram.senses_without_vectors.join("|")
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+mod conllu_output;
+mod json_output;
+mod sbxml_output;
+mod sparv_output;
+mod tab_output;
+mod vrt_output;
+
+use std::io;
+
+use serde_json::{Map, Value};
+
+use crate::wsd_application::DisambiguatedSentence;
+
+pub use self::conllu_output::ConlluOutput;
+pub use self::json_output::JsonOutput;
+pub use self::sbxml_output::SbXmlOutput;
+pub use self::sparv_output::SparvOutput;
+pub use self::tab_output::TabOutput;
+pub use self::vrt_output::VrtOutput;
+
+/// Writes disambiguated sentences, independently of the format they were read in.
+///
+/// Formats that keep the input markup, like [`SbXmlFormat`](crate::SbXmlFormat),
+/// are their own output and can only write what they have read. Input in
+/// another format is written in their layout by outputs like [`SbXmlOutput`],
+/// which write fresh markup.
+pub trait OutputFormat {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        sentence: &DisambiguatedSentence,
+    ) -> io::Result<()>;
+
+    /// Write whatever follows the last sentence.
+    fn finish(&mut self, _out: &mut dyn io::Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Forget a sentence that was read but written by another output format.
+    fn skip_sentence(&mut self) {}
+}
+
+impl OutputFormat for Box<dyn OutputFormat> {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        sentence: &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        self.as_mut().write_sentence(out, sentence)
+    }
+
+    fn finish(&mut self, out: &mut dyn io::Write) -> io::Result<()> {
+        self.as_mut().finish(out)
+    }
+
+    fn skip_sentence(&mut self) {
+        self.as_mut().skip_sentence()
+    }
+}
+
+/// Format senses as `sense:prob` joined by `separator`, most probable first.
+pub(crate) fn format_scored_senses(senses: &[String], scores: &[f32], separator: &str) -> String {
+    let mut scored: Vec<(&String, f32)> = senses.iter().zip(scores.iter().copied()).collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored
+        .iter()
+        .map(|(sense, score)| format!("{}:{:.3}", sense, score))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// A map from sense id to probability, most probable first, or to `null`
+/// for each candidate if the token has no scores.
+pub(crate) fn senses_map(senses: &[String], scores: Option<&[f32]>) -> Value {
+    let Some(scores) = scores else {
+        return senses
+            .iter()
+            .map(|sense| (sense.clone(), Value::Null))
+            .collect::<Map<String, Value>>()
+            .into();
+    };
+    let mut scored: Vec<(&String, f32)> = senses.iter().zip(scores.iter().copied()).collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored
+        .into_iter()
+        .map(|(sense, score)| {
+            // go through the shortest decimal form, so 0.7 is not written as 0.699999988079071
+            let score: f64 = score.to_string().parse().unwrap_or(f64::from(score));
+            (sense.clone(), Value::from(score))
+        })
+        .collect::<Map<String, Value>>()
+        .into()
+}
@@ -0,0 +1,67 @@
+use std::io;
+
+use super::{OutputFormat, format_scored_senses};
+use crate::source_format::DEFAULT_SENSE_KEY;
+use crate::wsd_application::DisambiguatedSentence;
+
+/// CoNLL-U written from the tokens alone, for input in another format.
+///
+/// The first lemma, like `utskott..nn`, is split into LEMMA `utskott` and XPOS
+/// `NN`, so that [`ConlluFormat`](crate::ConlluFormat) reads it back the same. The
+/// senses go in a MISC entry like `Sense=utskott..2:0.731,utskott..1:0.269`,
+/// or `Sense=utskott..1,utskott..2` if the token has no scores.
+#[derive(Debug)]
+pub struct ConlluOutput {
+    sense_key: String,
+}
+
+impl Default for ConlluOutput {
+    fn default() -> Self {
+        Self::new(DEFAULT_SENSE_KEY)
+    }
+}
+
+impl ConlluOutput {
+    pub fn new(sense_key: impl Into<String>) -> Self {
+        Self {
+            sense_key: sense_key.into(),
+        }
+    }
+}
+
+impl OutputFormat for ConlluOutput {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        (lts, scores): &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        for (i, (lt, scores)) in lts.iter().zip(scores).enumerate() {
+            let (lemma, xpos) = match lt.possible_lemmas().first() {
+                None => ("_".to_string(), "_".to_string()),
+                Some(lemma) => match lemma.rsplit_once("..") {
+                    Some((lemma, pos)) => (lemma.to_string(), pos.to_uppercase()),
+                    None => (lemma.clone(), "_".to_string()),
+                },
+            };
+            let senses = match scores {
+                Some(scores) => format_scored_senses(lt.possible_senses(), scores, ","),
+                None => lt.possible_senses().join(","),
+            };
+            let misc = if senses.is_empty() {
+                "_".to_string()
+            } else {
+                format!("{}={}", self.sense_key, senses)
+            };
+            writeln!(
+                out,
+                "{}\t{}\t{}\t_\t{}\t_\t_\t_\t_\t{}",
+                i + 1,
+                lt.word(),
+                lemma,
+                xpos,
+                misc
+            )?;
+        }
+        writeln!(out)
+    }
+}
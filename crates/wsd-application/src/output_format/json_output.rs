@@ -0,0 +1,40 @@
+use std::io;
+
+use serde_json::{Map, Value};
+
+use super::{OutputFormat, senses_map};
+use crate::wsd_application::DisambiguatedSentence;
+
+/// One sentence per line as a JSON array of tokens with `word`, `lemgrams`,
+/// `lemmas` and `senses`, in the layout [`JsonlFormat`](crate::JsonlFormat) reads.
+///
+/// `senses` maps each candidate sense to its probability, or to `null` if the
+/// token has no scores.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsonOutput;
+
+impl OutputFormat for JsonOutput {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        (lts, scores): &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        let tokens: Vec<Map<String, Value>> = lts
+            .iter()
+            .zip(scores)
+            .map(|(lt, scores)| {
+                let mut token = Map::new();
+                token.insert("word".into(), lt.word().into());
+                token.insert("lemgrams".into(), lt.possible_lemgrams().into());
+                token.insert("lemmas".into(), lt.possible_lemmas().into());
+                token.insert(
+                    "senses".into(),
+                    senses_map(lt.possible_senses(), scores.as_deref()),
+                );
+                token
+            })
+            .collect();
+        serde_json::to_writer(&mut *out, &tokens)?;
+        writeln!(out)
+    }
+}
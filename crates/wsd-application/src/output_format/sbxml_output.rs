@@ -0,0 +1,78 @@
+use std::io;
+
+use quick_xml::escape::escape;
+
+use super::{OutputFormat, format_scored_senses};
+use crate::wsd_application::DisambiguatedSentence;
+
+/// Sparv's XML export written from the tokens alone, for input in another format.
+///
+/// The sentences are `<sentence>` elements in a `<corpus>` root, and each
+/// token is a `<token>` with its lemgrams in `lex` and its senses in `sense`,
+/// like `|anslag..1:0.731|anslag..2:0.269|`, or the candidates without
+/// scores if it has none. Tokens without lemgrams get the part of speech of
+/// their lemma, like `RG` for `2018..rg`, in `pos`.
+#[derive(Debug, Default)]
+pub struct SbXmlOutput {
+    started: bool,
+}
+
+impl SbXmlOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn start(&mut self, out: &mut dyn io::Write) -> io::Result<()> {
+        if !self.started {
+            writeln!(out, "<corpus>")?;
+            self.started = true;
+        }
+        Ok(())
+    }
+}
+
+impl OutputFormat for SbXmlOutput {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        (lts, scores): &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        self.start(out)?;
+        writeln!(out, "<sentence>")?;
+        for (lt, scores) in lts.iter().zip(scores) {
+            let senses = match scores {
+                Some(scores) => format_scored_senses(lt.possible_senses(), scores, "|"),
+                None => lt.possible_senses().join("|"),
+            };
+            let pos = match (lt.possible_lemgrams(), lt.possible_lemmas().first()) {
+                ([], Some(lemma)) => lemma
+                    .rsplit_once("..")
+                    .map(|(_, pos)| format!(" pos=\"{}\"", escape(pos.to_uppercase())))
+                    .unwrap_or_default(),
+                _ => String::new(),
+            };
+            writeln!(
+                out,
+                "<token{} lex=\"{}\" sense=\"{}\">{}</token>",
+                pos,
+                escape(to_set(&lt.possible_lemgrams().join("|"))),
+                escape(to_set(&senses)),
+                escape(lt.word())
+            )?;
+        }
+        writeln!(out, "</sentence>")
+    }
+
+    fn finish(&mut self, out: &mut dyn io::Write) -> io::Result<()> {
+        self.start(out)?;
+        writeln!(out, "</corpus>")
+    }
+}
+
+/// A Sparv set value like `|a|b|`, or `|` if it is empty.
+fn to_set(values: &str) -> String {
+    match values {
+        "" => "|".to_string(),
+        values => format!("|{}|", values),
+    }
+}
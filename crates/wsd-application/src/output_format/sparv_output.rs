@@ -0,0 +1,32 @@
+use std::io;
+
+use super::{OutputFormat, format_scored_senses};
+use crate::wsd_application::DisambiguatedSentence;
+
+/// Like [`TabOutput`](super::TabOutput), but with the scores as a Sparv set of
+/// `sense:prob` pairs, most probable first, like `|anslag..1:0.731|anslag..2:0.269|`.
+///
+/// Tokens without scores get the empty set `|`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SparvOutput;
+
+impl OutputFormat for SparvOutput {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        (lts, scores): &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        for (lt, scores) in lts.iter().zip(scores) {
+            match scores {
+                None => writeln!(out, "{}\t|", lt)?,
+                Some(scores) => writeln!(
+                    out,
+                    "{}\t|{}|",
+                    lt,
+                    format_scored_senses(lt.possible_senses(), scores, "|")
+                )?,
+            }
+        }
+        writeln!(out)
+    }
+}
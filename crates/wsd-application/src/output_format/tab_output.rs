@@ -0,0 +1,37 @@
+use std::io;
+
+use super::OutputFormat;
+use crate::wsd_application::DisambiguatedSentence;
+
+/// One token per line in the tab format, followed by a tab and the `|`-separated
+/// scores of its senses, or `_` if it has none.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TabOutput;
+
+impl OutputFormat for TabOutput {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        (lts, scores): &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        for (lt, scores) in lts.iter().zip(scores) {
+            match scores {
+                None => writeln!(out, "{}\t_", lt)?,
+                Some(scores) => writeln!(out, "{}\t{}", lt, join_to_string(scores))?,
+            }
+        }
+        writeln!(out)
+    }
+}
+
+fn join_to_string(vs: &[f32]) -> String {
+    if vs.is_empty() {
+        return String::new();
+    }
+    let mut out = String::with_capacity(vs.len() * 2);
+    out.push_str(&vs[0].to_string());
+    for v in &vs[1..] {
+        out.push_str(&format!("|{}", v));
+    }
+    out
+}
@@ -0,0 +1,86 @@
+use std::io;
+
+use super::{OutputFormat, format_scored_senses};
+use crate::VrtAttributes;
+use crate::wsd_application::DisambiguatedSentence;
+
+/// Korp's verticalized text written from the tokens alone, for input in
+/// another format.
+///
+/// A header declares the positional attributes, with the scores attribute
+/// last. The word, lemma and sense attributes are filled in from each token
+/// and the others are `_`. Each sentence is a `<sentence>` element.
+#[derive(Debug, Default)]
+pub struct VrtOutput {
+    attributes: VrtAttributes,
+    header_written: bool,
+}
+
+impl VrtOutput {
+    pub fn new(attributes: VrtAttributes) -> Self {
+        Self {
+            attributes,
+            header_written: false,
+        }
+    }
+}
+
+impl OutputFormat for VrtOutput {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        (lts, scores): &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        let attributes = &self.attributes;
+        if !self.header_written {
+            writeln!(
+                out,
+                "<!-- #vrt positional-attributes: {} {} -->",
+                attributes.positional.join(" "),
+                attributes.scores
+            )?;
+            self.header_written = true;
+        }
+        writeln!(out, "<sentence>")?;
+        for (lt, scores) in lts.iter().zip(scores) {
+            // the lemma attribute holds lemgrams if there are any
+            let lemmas = match lt.possible_lemgrams() {
+                [] => lt.possible_lemmas(),
+                lemgrams => lemgrams,
+            };
+            let columns: Vec<String> = attributes
+                .positional
+                .iter()
+                .map(|name| {
+                    if *name == attributes.word {
+                        lt.word().to_string()
+                    } else if *name == attributes.lemma {
+                        to_set(lemmas)
+                    } else if *name == attributes.sense {
+                        to_set(lt.possible_senses())
+                    } else {
+                        "_".to_string()
+                    }
+                })
+                .collect();
+            match scores {
+                Some(scores) => writeln!(
+                    out,
+                    "{}\t|{}|",
+                    columns.join("\t"),
+                    format_scored_senses(lt.possible_senses(), scores, "|")
+                )?,
+                None => writeln!(out, "{}\t|", columns.join("\t"))?,
+            }
+        }
+        writeln!(out, "</sentence>")
+    }
+}
+
+/// A Korp set value like `|a|b|`, or `|` if it is empty.
+fn to_set(values: &[String]) -> String {
+    match values {
+        [] => "|".to_string(),
+        values => format!("|{}|", values.join("|")),
+    }
+}
@@ -0,0 +1,309 @@
+use hashbrown::HashMap;
+use process_corpus::LemmaToken;
+use saldo::{PRIM, SaldoLexicon};
+
+use crate::{DisambiguatedSentence, UsageError, WSDApplication};
+
+/// What the random walk of a [`PageRankWSD`] is seeded with, chosen with `-pageRankMode=`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum PageRankMode {
+    /// One walk per target, seeded with the senses of its context but not
+    /// its own, like UKB's `ppr_w2w`.
+    #[default]
+    Word,
+    /// One walk per sentence, seeded with the senses of all its tokens, like
+    /// UKB's `ppr`. Faster, but each target's own senses are seeded too.
+    Sentence,
+}
+
+impl std::str::FromStr for PageRankMode {
+    type Err = UsageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "word" => Ok(Self::Word),
+            "sentence" => Ok(Self::Sentence),
+            _ => Err(UsageError::BadValue {
+                param: "-pageRankMode=".into(),
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PageRankOptions {
+    /// The probability of following an edge rather than jumping back to a seed.
+    pub damping: f32,
+    pub iterations: usize,
+    /// The weight of secondary descriptor edges; primary ones weigh 1.
+    pub secondary_weight: f32,
+    /// The number of tokens on each side that seed the walk of a target in
+    /// [`PageRankMode::Word`], or the whole sentence if not given.
+    pub context_width: Option<usize>,
+    pub mode: PageRankMode,
+}
+
+impl Default for PageRankOptions {
+    fn default() -> Self {
+        Self {
+            damping: 0.85,
+            iterations: 30,
+            secondary_weight: 1.0,
+            context_width: None,
+            mode: PageRankMode::default(),
+        }
+    }
+}
+
+impl PageRankOptions {
+    /// Override the options given as `-damping=`, `-iterations=`,
+    /// `-secondaryWeight=`, `-contextWidth=` and `-pageRankMode=` in `argv`.
+    pub fn update_from_args(&mut self, argv: &[String]) -> Result<(), UsageError> {
+        for a in argv {
+            if let Some(val) = a.strip_prefix("-damping=") {
+                self.damping = val
+                    .parse()
+                    .ok()
+                    .filter(|d| (0.0..1.0).contains(d))
+                    .ok_or_else(|| UsageError::BadValue {
+                        param: "-damping=".into(),
+                        value: val.to_string(),
+                    })?;
+            } else if let Some(val) = a.strip_prefix("-iterations=") {
+                self.iterations = val.parse().map_err(|_err| UsageError::BadValue {
+                    param: "-iterations=".into(),
+                    value: val.to_string(),
+                })?;
+            } else if let Some(val) = a.strip_prefix("-secondaryWeight=") {
+                self.secondary_weight =
+                    val.parse()
+                        .ok()
+                        .filter(|w: &f32| *w >= 0.0)
+                        .ok_or_else(|| UsageError::BadValue {
+                            param: "-secondaryWeight=".into(),
+                            value: val.to_string(),
+                        })?;
+            } else if let Some(val) = a.strip_prefix("-contextWidth=") {
+                self.context_width = Some(val.parse().map_err(|_err| UsageError::BadValue {
+                    param: "-contextWidth=".into(),
+                    value: val.to_string(),
+                })?);
+            } else if let Some(val) = a.strip_prefix("-pageRankMode=") {
+                self.mode = val.parse()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Knowledge-based WSD with personalized PageRank over the SALDO graph.
+///
+/// The senses are nodes, linked in both directions to their primary and
+/// secondary descriptors, except `PRIM..1`. A random walk that restarts at
+/// the candidate senses of the context ranks the senses of each target, so
+/// no trained vectors are needed.
+pub struct PageRankWSD {
+    options: PageRankOptions,
+    nodes: HashMap<String, usize>,
+    /// The neighbours of each node, with the weight of the edge divided by
+    /// the node's total edge weight.
+    edges: Vec<Vec<(usize, f32)>>,
+}
+
+impl PageRankWSD {
+    pub fn new(options: PageRankOptions, saldo: &SaldoLexicon) -> Self {
+        let mut ids: Vec<&str> = saldo
+            .entries()
+            .map(|e| e.get_id().as_str())
+            .filter(|id| *id != PRIM)
+            .collect();
+        // sorted, so that sums are taken in the same order on every run
+        ids.sort_unstable();
+        let nodes: HashMap<String, usize> = ids
+            .iter()
+            .enumerate()
+            .map(|(n, id)| (id.to_string(), n))
+            .collect();
+        let mut edges: Vec<Vec<(usize, f32)>> = vec![Vec::new(); ids.len()];
+        for (n, id) in ids.iter().enumerate() {
+            let Some(entry) = saldo.get(id) else {
+                continue;
+            };
+            let descriptors = entry
+                .mf()
+                .map(|mf| (mf, 1.0))
+                .into_iter()
+                .chain(entry.pf().iter().map(|pf| (pf, options.secondary_weight)));
+            for (descriptor, weight) in descriptors.filter(|(_, w)| *w > 0.0) {
+                if let Some(&m) = nodes.get(descriptor.as_str()) {
+                    edges[n].push((m, weight));
+                    edges[m].push((n, weight));
+                }
+            }
+        }
+        for node_edges in &mut edges {
+            let total: f32 = node_edges.iter().map(|(_, w)| w).sum();
+            for (_, w) in node_edges.iter_mut() {
+                *w /= total;
+            }
+        }
+        Self {
+            options,
+            nodes,
+            edges,
+        }
+    }
+
+    pub fn new_as_shared(
+        saldo: Option<&SaldoLexicon>,
+        argv: &[String],
+    ) -> Result<crate::SharedWSDApplication, UsageError> {
+        Ok(std::sync::Arc::new(Self::from_args(saldo, argv)?))
+    }
+
+    /// Create from command-line flags and the lexicon given with `-saldo=`.
+    pub fn from_args(saldo: Option<&SaldoLexicon>, argv: &[String]) -> Result<Self, UsageError> {
+        let saldo = saldo.ok_or_else(|| UsageError::missing_required_argument("-saldo="))?;
+        let mut options = PageRankOptions::default();
+        options.update_from_args(argv)?;
+        log::info!("Building SALDO graph...");
+        Ok(Self::new(options, saldo))
+    }
+
+    /// The number of senses in the graph.
+    pub fn num_nodes(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// The positions of the tokens that seed the walk for the target `i`.
+    fn window(&self, i: usize, len: usize) -> std::ops::Range<usize> {
+        match self.options.context_width {
+            Some(width) => i.saturating_sub(width)..len.min(i.saturating_add(width) + 1),
+            None => 0..len,
+        }
+    }
+
+    /// The restart distribution: each token's mass spread evenly over its
+    /// senses in the graph.
+    fn seeds<'a>(&self, lts: impl Iterator<Item = &'a LemmaToken>) -> Vec<(usize, f32)> {
+        let mut seeds = Vec::new();
+        let mut num_tokens = 0;
+        for lt in lts {
+            let senses: Vec<usize> = lt
+                .possible_senses()
+                .iter()
+                .filter_map(|sense| self.nodes.get(sense.as_str()).copied())
+                .collect();
+            if senses.is_empty() {
+                continue;
+            }
+            num_tokens += 1;
+            let mass = 1.0 / senses.len() as f32;
+            seeds.extend(senses.into_iter().map(|n| (n, mass)));
+        }
+        for (_, mass) in &mut seeds {
+            *mass /= num_tokens as f32;
+        }
+        seeds
+    }
+
+    /// Personalized PageRank by power iteration. The mass of nodes without
+    /// edges goes back to the seeds.
+    fn page_rank(&self, seeds: &[(usize, f32)]) -> Vec<f32> {
+        let damping = self.options.damping;
+        let mut rank = vec![0f32; self.edges.len()];
+        for &(n, mass) in seeds {
+            rank[n] += mass;
+        }
+        let mut next = vec![0f32; self.edges.len()];
+        for _ in 0..self.options.iterations {
+            next.fill(0.0);
+            let mut restart = 1.0 - damping;
+            for (n, &r) in rank.iter().enumerate() {
+                if r == 0.0 {
+                    continue;
+                }
+                if self.edges[n].is_empty() {
+                    restart += damping * r;
+                }
+                for &(m, w) in &self.edges[n] {
+                    next[m] += damping * r * w;
+                }
+            }
+            for &(n, mass) in seeds {
+                next[n] += restart * mass;
+            }
+            std::mem::swap(&mut rank, &mut next);
+        }
+        rank
+    }
+
+    /// The ranks of the senses of `lt`, normalized to probabilities, or `None`
+    /// if it has fewer than two senses or none of them got any rank.
+    fn score(&self, lt: &LemmaToken, rank: &[f32]) -> Option<Vec<f32>> {
+        if lt.possible_senses().len() < 2 {
+            return None;
+        }
+        let mut out: Vec<f32> = lt
+            .possible_senses()
+            .iter()
+            .map(|sense| self.nodes.get(sense.as_str()).map_or(0.0, |&n| rank[n]))
+            .collect();
+        let total: f32 = out.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        for p in &mut out {
+            *p /= total;
+        }
+        Some(out)
+    }
+
+    fn is_target(&self, lt: &LemmaToken) -> bool {
+        lt.possible_senses().len() >= 2
+            && lt
+                .possible_senses()
+                .iter()
+                .any(|sense| self.nodes.contains_key(sense.as_str()))
+    }
+}
+
+impl WSDApplication for PageRankWSD {
+    /// In [`PageRankMode::Sentence`], score all targets with one walk.
+    fn disambiguate_sentence(&self, lts: Vec<LemmaToken>) -> DisambiguatedSentence {
+        let disamb = match self.options.mode {
+            PageRankMode::Word => (0..lts.len()).map(|i| self.disambiguate(&lts, i)).collect(),
+            PageRankMode::Sentence if lts.iter().any(|lt| self.is_target(lt)) => {
+                let rank = self.page_rank(&self.seeds(lts.iter()));
+                lts.iter().map(|lt| self.score(lt, &rank)).collect()
+            }
+            PageRankMode::Sentence => vec![None; lts.len()],
+        };
+        (lts, disamb)
+    }
+
+    fn disambiguate(&self, lts: &[LemmaToken], i: usize) -> Option<Vec<f32>> {
+        if !self.is_target(&lts[i]) {
+            return None;
+        }
+        let seeds = match self.options.mode {
+            PageRankMode::Word => {
+                let window = self.window(i, lts.len());
+                let start = window.start;
+                self.seeds(
+                    lts[window]
+                        .iter()
+                        .enumerate()
+                        .filter(|(k, _)| start + k != i)
+                        .map(|(_, lt)| lt),
+                )
+            }
+            PageRankMode::Sentence => self.seeds(lts.iter()),
+        };
+        if seeds.is_empty() {
+            return None;
+        }
+        self.score(&lts[i], &self.page_rank(&seeds))
+    }
+}
@@ -0,0 +1,116 @@
+use hashbrown::HashMap;
+use saldo::{PRIM, SaldoEntry, SaldoId, SaldoLexicon};
+use w2v::Embeddings;
+
+use crate::UsageError;
+
+/// How a sense without a vector is approximated from its SALDO neighbours
+/// that have one, chosen with `-senseBackoff=`.
+///
+/// The vector is the weighted sum of the mean vector of each kind of
+/// neighbour, with the weights of the kinds that have no vectors left out.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SenseBackoff {
+    /// The weight of the primary descriptor.
+    pub primary: f32,
+    /// The weight of the secondary descriptors.
+    pub secondary: f32,
+    /// The weight of the senses this is a primary or secondary descriptor of.
+    pub inverse: f32,
+}
+
+impl Default for SenseBackoff {
+    fn default() -> Self {
+        Self {
+            primary: 1.0,
+            secondary: 0.5,
+            inverse: 0.5,
+        }
+    }
+}
+
+impl SenseBackoff {
+    /// The neighbours with vectors of each sense in `saldo` that has no
+    /// vector in `sense_vectors`, with weights that sum to 1. Senses none of
+    /// whose neighbours have vectors are left out.
+    pub fn neighbours(
+        &self,
+        saldo: &SaldoLexicon,
+        sense_vectors: &dyn Embeddings,
+    ) -> HashMap<String, Vec<(String, f32)>> {
+        let mut out = HashMap::new();
+        for entry in saldo.entries() {
+            let id = entry.get_id().as_str();
+            if id == PRIM || sense_vectors.contains(id) {
+                continue;
+            }
+            if let Some(neighbours) = self.entry_neighbours(entry, sense_vectors) {
+                out.insert(id.to_string(), neighbours);
+            }
+        }
+        out
+    }
+
+    fn entry_neighbours(
+        &self,
+        entry: &SaldoEntry,
+        sense_vectors: &dyn Embeddings,
+    ) -> Option<Vec<(String, f32)>> {
+        let with_vector = |ids: &mut dyn Iterator<Item = &SaldoId>| -> Vec<String> {
+            ids.map(SaldoId::as_str)
+                .filter(|id| *id != PRIM && sense_vectors.contains(id))
+                .map(String::from)
+                .collect()
+        };
+        let kinds = [
+            (self.primary, with_vector(&mut entry.mf().into_iter())),
+            (self.secondary, with_vector(&mut entry.pf().iter())),
+            (
+                self.inverse,
+                with_vector(&mut entry.inv_mf().iter().chain(entry.inv_pf())),
+            ),
+        ];
+        let total: f32 = kinds
+            .iter()
+            .filter(|(w, ids)| *w > 0.0 && !ids.is_empty())
+            .map(|(w, _)| w)
+            .sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut neighbours = Vec::new();
+        for (weight, ids) in kinds.into_iter().filter(|(w, _)| *w > 0.0) {
+            let each = weight / total / ids.len() as f32;
+            neighbours.extend(ids.into_iter().map(|id| (id, each)));
+        }
+        Some(neighbours)
+    }
+}
+
+impl std::str::FromStr for SenseBackoff {
+    type Err = UsageError;
+
+    /// Parse `true` for the default weights, or `PRIMARY,SECONDARY,INVERSE`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "true" {
+            return Ok(Self::default());
+        }
+        let weights: Option<Vec<f32>> = s
+            .split(',')
+            .map(|w| w.parse().ok().filter(|w: &f32| w.is_finite() && *w >= 0.0))
+            .collect();
+        match weights.as_deref() {
+            Some(&[primary, secondary, inverse]) if primary + secondary + inverse > 0.0 => {
+                Ok(Self {
+                    primary,
+                    secondary,
+                    inverse,
+                })
+            }
+            _ => Err(UsageError::BadValue {
+                param: "-senseBackoff=".into(),
+                value: s.to_string(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::{collections::BTreeMap, fmt, io};
+
+use process_corpus::{CorpusError, LemmaToken};
+
+use crate::wsd_application::{DisambiguateOptions, disambiguate_documents};
+use crate::{DisambiguatedSentence, SharedWSDApplication, SourceFormat};
+
+/// How often each sense of a lemma is used in a corpus, estimated by
+/// disambiguating its occurrences.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LemmaRatios {
+    /// Occurrences of the lemma.
+    pub occurrences: usize,
+    /// Occurrences the application gave a sense distribution for.
+    pub scored: usize,
+    /// The sum of each sense's probability over the scored occurrences.
+    pub expected: BTreeMap<String, f64>,
+    /// The number of scored occurrences where each sense was the most probable.
+    pub argmax: BTreeMap<String, usize>,
+}
+
+impl LemmaRatios {
+    fn add(&mut self, lt: &LemmaToken, scores: Option<&[f32]>) {
+        self.occurrences += 1;
+        let Some(scores) = scores.filter(|scores| !scores.is_empty()) else {
+            return;
+        };
+        self.scored += 1;
+        let mut best: Option<(&String, f32)> = None;
+        for (sense, &p) in lt.possible_senses().iter().zip(scores) {
+            *self.expected.entry(sense.clone()).or_default() += f64::from(p);
+            self.argmax.entry(sense.clone()).or_default();
+            if best.is_none_or(|(_, q)| p > q) {
+                best = Some((sense, p));
+            }
+        }
+        if let Some((sense, _)) = best {
+            *self.argmax.entry(sense.clone()).or_default() += 1;
+        }
+    }
+
+    /// The expected share of the scored occurrences that use `sense`.
+    pub fn expected_ratio(&self, sense: &str) -> f64 {
+        self.ratio(self.expected.get(sense).copied().unwrap_or_default())
+    }
+
+    /// The share of the scored occurrences where `sense` was the most probable.
+    pub fn argmax_ratio(&self, sense: &str) -> f64 {
+        self.ratio(self.argmax.get(sense).copied().unwrap_or_default() as f64)
+    }
+
+    fn ratio(&self, n: f64) -> f64 {
+        if self.scored == 0 {
+            0.0
+        } else {
+            n / self.scored as f64
+        }
+    }
+}
+
+/// The sense ratios of each target lemma, in the order they were given.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SenseRatios {
+    pub lemmas: Vec<(String, LemmaRatios)>,
+}
+
+impl SenseRatios {
+    /// Targets like `anslag..nn` match that lemma, and `anslag` matches it
+    /// with any part of speech.
+    pub fn new<S: AsRef<str>>(targets: &[S]) -> Self {
+        Self {
+            lemmas: targets
+                .iter()
+                .map(|target| (target.as_ref().to_string(), LemmaRatios::default()))
+                .collect(),
+        }
+    }
+}
+
+/// The index of the first of `targets` that `lt` is an occurrence of.
+fn find_target<S: AsRef<str>>(targets: &[S], lt: &LemmaToken) -> Option<usize> {
+    targets.iter().position(|target| {
+        let target = target.as_ref();
+        lt.possible_lemmas().iter().any(|lemma| {
+            lemma == target
+                || lemma
+                    .split_once("..")
+                    .is_some_and(|(base, _)| base == target)
+        })
+    })
+}
+
+impl fmt::Display for SenseRatios {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (target, ratios)) in self.lemmas.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(
+                f,
+                "{}\toccurrences={}\tscored={}",
+                target, ratios.occurrences, ratios.scored
+            )?;
+            writeln!(f, "sense\texpected\targmax")?;
+            let mut senses: Vec<&String> = ratios.expected.keys().collect();
+            senses.sort_by(|a, b| {
+                ratios
+                    .expected_ratio(b)
+                    .total_cmp(&ratios.expected_ratio(a))
+                    .then(a.cmp(b))
+            });
+            for sense in senses {
+                writeln!(
+                    f,
+                    "{}\t{:.3}\t{:.3}",
+                    sense,
+                    ratios.expected_ratio(sense),
+                    ratios.argmax_ratio(sense)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Disambiguate the occurrences of `targets` in the sentences `f` reads, with
+/// the context the application asks for, and collect their sense ratios.
+/// Other tokens are only used as context.
+pub fn sense_ratios<S: AsRef<str> + Sync>(
+    wsd: SharedWSDApplication,
+    reader: &mut dyn io::BufRead,
+    f: &mut dyn SourceFormat,
+    targets: &[S],
+    batch_size: usize,
+    max_sen: usize,
+) -> Result<SenseRatios, CorpusError> {
+    let mut ratios = SenseRatios::new(targets);
+    let opts = DisambiguateOptions {
+        batch_size,
+        max_sen,
+        ..DisambiguateOptions::default()
+    };
+    let is_target = |lt: &LemmaToken| find_target(targets, lt).is_some();
+    let mut add = |f: &mut dyn SourceFormat, (lts, scores): DisambiguatedSentence| {
+        f.output().skip_sentence();
+        for (lt, scores) in lts.iter().zip(&scores) {
+            if let Some(target) = find_target(targets, lt) {
+                ratios.lemmas[target].1.add(lt, scores.as_deref());
+            }
+        }
+        Ok(())
+    };
+    disambiguate_documents(wsd.as_ref(), reader, f, opts, &is_target, &mut add)?;
+    Ok(ratios)
+}
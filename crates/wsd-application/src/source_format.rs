@@ -1,25 +1,161 @@
+mod conllu_format;
+mod jsonl_format;
+mod sbxml_format;
 mod tab_format;
+mod vrt_format;
 
 use std::io;
 
-use process_corpus::LemmaToken;
+use process_corpus::{CorpusError, LemmaToken};
 
-pub use self::tab_format::TabFormat;
+use crate::OutputFormat;
+
+pub use self::conllu_format::{ConlluFormat, DEFAULT_SENSE_KEY};
+pub use self::jsonl_format::JsonlFormat;
+pub use self::sbxml_format::SbXmlFormat;
+pub use self::tab_format::{TabColumn, TabColumns, TabFormat};
+pub use self::vrt_format::{VrtAttributes, VrtFormat};
 
 pub trait SourceFormat {
+    /// Read the next sentence, or `None` at the end of the input.
+    fn read_sentence(
+        &mut self,
+        reader: &mut dyn io::BufRead,
+    ) -> Result<Option<Vec<LemmaToken>>, CorpusError>;
+
+    /// Read up to `batch_size` sentences; fewer only at the end of the input.
     fn read_sentences(
-        &self,
+        &mut self,
         reader: &mut dyn io::BufRead,
         batch_size: usize,
-    ) -> std::io::Result<Vec<Vec<LemmaToken>>>;
+    ) -> Result<Vec<Vec<LemmaToken>>, CorpusError> {
+        let mut out = Vec::new();
+        while out.len() < batch_size {
+            match self.read_sentence(reader)? {
+                Some(lts) => out.push(lts),
+                None => break,
+            }
+        }
+        Ok(out)
+    }
+
+    /// Read the rest of the input, split into documents.
+    fn read_documents(
+        &mut self,
+        reader: &mut dyn io::BufRead,
+    ) -> Result<Vec<Vec<Vec<LemmaToken>>>, CorpusError> {
+        let mut documents: Vec<Vec<Vec<LemmaToken>>> = Vec::new();
+        let mut breaks = self.document_breaks();
+        while let Some(lts) = self.read_sentence(reader)? {
+            match documents.last_mut() {
+                Some(document) if self.document_breaks() == breaks => document.push(lts),
+                _ => documents.push(vec![lts]),
+            }
+            breaks = self.document_breaks();
+        }
+        Ok(documents)
+    }
+
+    /// Iterate over the sentences of `reader`.
+    fn sentences<'a>(&'a mut self, reader: &'a mut dyn io::BufRead) -> Sentences<'a>
+    where
+        Self: Sized,
+    {
+        Sentences::new(self, reader)
+    }
+
+    /// The number of malformed input lines that were repaired or skipped.
+    fn num_malformed(&self) -> usize {
+        0
+    }
+
+    /// The number of document boundaries read so far, like `<text>` in VRT.
+    ///
+    /// A sentence starts a new document if this grew while it was read.
+    /// Formats without documents are one document.
+    fn document_breaks(&self) -> usize {
+        0
+    }
+
+    /// The writer that puts disambiguated sentences back in this format.
+    fn output(&mut self) -> &mut dyn OutputFormat;
 }
 
 impl SourceFormat for Box<dyn SourceFormat> {
+    fn read_sentence(
+        &mut self,
+        reader: &mut dyn io::BufRead,
+    ) -> Result<Option<Vec<LemmaToken>>, CorpusError> {
+        self.as_mut().read_sentence(reader)
+    }
+
     fn read_sentences(
-        &self,
+        &mut self,
         reader: &mut dyn io::BufRead,
         batch_size: usize,
-    ) -> std::io::Result<Vec<Vec<LemmaToken>>> {
-        self.as_ref().read_sentences(reader, batch_size)
+    ) -> Result<Vec<Vec<LemmaToken>>, CorpusError> {
+        self.as_mut().read_sentences(reader, batch_size)
+    }
+
+    fn num_malformed(&self) -> usize {
+        self.as_ref().num_malformed()
+    }
+
+    fn document_breaks(&self) -> usize {
+        self.as_ref().document_breaks()
+    }
+
+    fn output(&mut self) -> &mut dyn OutputFormat {
+        self.as_mut().output()
+    }
+}
+
+/// An iterator over the sentences a [`SourceFormat`] reads.
+///
+/// It ends after the first error.
+pub struct Sentences<'a> {
+    format: &'a mut dyn SourceFormat,
+    reader: &'a mut dyn io::BufRead,
+    done: bool,
+}
+
+impl<'a> Sentences<'a> {
+    pub fn new(format: &'a mut dyn SourceFormat, reader: &'a mut dyn io::BufRead) -> Self {
+        Self {
+            format,
+            reader,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for Sentences<'_> {
+    type Item = Result<Vec<LemmaToken>, CorpusError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.format.read_sentence(self.reader).transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+impl std::iter::FusedIterator for Sentences<'_> {}
+
+/// Drop a Sparv-style `:score` suffix from a lemgram or sense id.
+pub(crate) fn strip_score(value: &str) -> &str {
+    match value.rsplit_once(':') {
+        Some((id, score)) if score.parse::<f64>().is_ok() => id,
+        _ => value,
+    }
+}
+
+/// `ansökan..nn.1` -> `ansökan..nn`
+pub(crate) fn lemgram_to_lemma(lemgram: &str) -> &str {
+    match lemgram.rsplit_once('.') {
+        Some((lemma, n)) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => lemma,
+        _ => lemgram,
     }
 }
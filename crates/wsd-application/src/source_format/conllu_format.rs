@@ -0,0 +1,246 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+};
+
+use process_corpus::{CorpusError, LemmaToken, ParseError, ParseErrorKind};
+
+use super::{SourceFormat, strip_score};
+use crate::output_format::{OutputFormat, format_scored_senses};
+use crate::wsd_application::DisambiguatedSentence;
+
+/// The MISC key that holds the candidate senses unless another is given.
+pub const DEFAULT_SENSE_KEY: &str = "Sense";
+
+/// CoNLL-U, with lemmas from LEMMA and candidate senses in a MISC entry like
+/// `Sense=utskott..1,utskott..2`.
+///
+/// A SUC tag in XPOS is added to the lemma, `utskott..nn`, as in the tab format.
+///
+/// Comments, multiword token ranges and empty nodes are kept but not
+/// disambiguated. A `# newdoc` comment, or a blank line after the one that
+/// ends a sentence, starts a document. The writer replaces the sense entry of each scored word
+/// with `Sense=utskott..2:0.731,utskott..1:0.269`.
+#[derive(Debug)]
+pub struct ConlluFormat {
+    sense_key: String,
+    /// The lines of the sentence being read.
+    pending: Vec<Line>,
+    /// The lines of each sentence that has been read but not written.
+    sentences: VecDeque<Vec<Line>>,
+    line_number: usize,
+    document_breaks: usize,
+}
+
+#[derive(Debug)]
+struct Line {
+    text: String,
+    /// The position of the word among the sentence's tokens, if this is a word line.
+    token: Option<usize>,
+}
+
+const NUM_COLUMNS: usize = 10;
+const LEMMA: usize = 2;
+const XPOS: usize = 4;
+const MISC: usize = 9;
+
+impl Default for ConlluFormat {
+    fn default() -> Self {
+        Self::new(DEFAULT_SENSE_KEY)
+    }
+}
+
+impl ConlluFormat {
+    pub fn new(sense_key: impl Into<String>) -> Self {
+        Self {
+            sense_key: sense_key.into(),
+            pending: Vec::new(),
+            sentences: VecDeque::new(),
+            line_number: 0,
+            document_breaks: 0,
+        }
+    }
+
+    /// Whether the pending lines hold more than comments and blank lines.
+    fn has_word_lines(&self) -> bool {
+        self.pending
+            .iter()
+            .any(|line| !line.text.is_empty() && !line.text.starts_with('#'))
+    }
+
+    /// Parse a word line, or return `None` for a multiword token or empty node.
+    fn parse_word(&self, line: &str) -> Result<Option<LemmaToken>, ParseError> {
+        let cols: Vec<&str> = line.split('\t').collect();
+        if cols.len() < NUM_COLUMNS {
+            return Err(ParseError::new(
+                self.line_number,
+                cols.len() + 1,
+                line,
+                ParseErrorKind::MissingColumns {
+                    expected: NUM_COLUMNS,
+                    found: cols.len(),
+                },
+            ));
+        }
+        let id = cols[0];
+        if id.contains(['-', '.']) {
+            return Ok(None);
+        }
+        if id.parse::<usize>().is_err() {
+            return Err(ParseError::new(
+                self.line_number,
+                1,
+                line,
+                ParseErrorKind::Other(format!("invalid word id '{}'", id)),
+            ));
+        }
+        let lemmas = match (cols[LEMMA], cols[XPOS]) {
+            ("_", _) => Vec::new(),
+            (lemma, "_") => vec![lemma.to_string()],
+            // the context model knows lemmas as `lemma..pos` with lowercase SUC tags
+            (lemma, xpos) => {
+                let pos = xpos.split('|').next().unwrap_or(xpos);
+                vec![format!("{}..{}", lemma, pos.to_lowercase())]
+            }
+        };
+        let senses = cols[MISC]
+            .split('|')
+            .find_map(|entry| entry.strip_prefix(&self.sense_key)?.strip_prefix('='))
+            .map(|value| {
+                value
+                    .split(',')
+                    .filter(|v| !v.is_empty())
+                    .map(|v| strip_score(v).to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Some(LemmaToken::new(
+            id,
+            cols[1],
+            Vec::new(),
+            lemmas,
+            senses,
+        )))
+    }
+
+    /// Replace or add the sense entry in the MISC column of `line`.
+    fn with_senses(&self, line: &str, senses: &str) -> String {
+        let mut cols: Vec<&str> = line.split('\t').collect();
+        let entry = format!("{}={}", self.sense_key, senses);
+        let mut misc: Vec<&str> = match cols[MISC] {
+            "_" => Vec::new(),
+            misc => misc.split('|').collect(),
+        };
+        let prefix = format!("{}=", self.sense_key);
+        match misc.iter().position(|e| e.starts_with(&prefix)) {
+            Some(i) => misc[i] = &entry,
+            None => misc.push(&entry),
+        }
+        let misc = misc.join("|");
+        cols[MISC] = &misc;
+        cols.join("\t")
+    }
+}
+
+impl SourceFormat for ConlluFormat {
+    fn read_sentence(
+        &mut self,
+        reader: &mut dyn BufRead,
+    ) -> Result<Option<Vec<LemmaToken>>, CorpusError> {
+        let mut lts = Vec::new();
+        let mut buf = String::new();
+        loop {
+            buf.clear();
+            if reader.read_line(&mut buf)? == 0 {
+                if self.has_word_lines() {
+                    // the last sentence has no blank line after it
+                    self.sentences.push_back(std::mem::take(&mut self.pending));
+                    return Ok(Some(lts));
+                }
+                return Ok(None);
+            }
+            self.line_number += 1;
+            let text = buf.trim_end_matches(['\n', '\r']).to_string();
+            if text.is_empty() {
+                let is_sentence = self.has_word_lines();
+                self.pending.push(Line { text, token: None });
+                if is_sentence {
+                    self.sentences.push_back(std::mem::take(&mut self.pending));
+                    return Ok(Some(lts));
+                }
+                self.document_breaks += 1;
+                continue;
+            }
+            let mut token = None;
+            if is_newdoc(&text) {
+                self.document_breaks += 1;
+            } else if !text.starts_with('#') {
+                if let Some(lt) = self.parse_word(&text)? {
+                    token = Some(lts.len());
+                    lts.push(lt);
+                }
+            }
+            self.pending.push(Line { text, token });
+        }
+    }
+
+    fn document_breaks(&self) -> usize {
+        self.document_breaks
+    }
+
+    fn output(&mut self) -> &mut dyn OutputFormat {
+        self
+    }
+}
+
+/// Whether `line` is a `# newdoc` comment, with or without an id.
+fn is_newdoc(line: &str) -> bool {
+    line.strip_prefix('#')
+        .map(str::trim_start)
+        .and_then(|rest| rest.strip_prefix("newdoc"))
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', '=']))
+}
+
+impl OutputFormat for ConlluFormat {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        (lts, scores): &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        let lines = self.sentences.pop_front().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "more sentences written than read",
+            )
+        })?;
+        for line in lines {
+            match line
+                .token
+                .and_then(|i| Some((lts.get(i)?, scores.get(i)?.as_ref()?)))
+            {
+                Some((lt, scores)) => {
+                    let senses = format_scored_senses(lt.possible_senses(), scores, ",");
+                    writeln!(out, "{}", self.with_senses(&line.text, &senses))?
+                }
+                None => writeln!(out, "{}", line.text)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn io::Write) -> io::Result<()> {
+        for line in self
+            .sentences
+            .drain(..)
+            .flatten()
+            .chain(self.pending.drain(..))
+        {
+            writeln!(out, "{}", line.text)?;
+        }
+        Ok(())
+    }
+
+    fn skip_sentence(&mut self) {
+        self.sentences.pop_front();
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+};
+
+use process_corpus::{CorpusError, LemmaToken, ParseError, ParseErrorKind};
+use serde_json::{Map, Value};
+
+use super::{SourceFormat, lemgram_to_lemma};
+use crate::output_format::{OutputFormat, senses_map};
+use crate::wsd_application::DisambiguatedSentence;
+
+type JsonToken = Map<String, Value>;
+
+/// JSON Lines with one sentence per line, as an array of token objects like
+/// `{"word": "anslag", "lemmas": ["anslag..nn"], "senses": ["anslag..1", "anslag..2"]}`.
+///
+/// `lemgrams` may be given instead of or besides `lemmas`. Other fields are
+/// passed through. Blank lines separate documents, and are written back as one. The writer replaces `senses` with a map from sense id to
+/// probability, or to `null` if the token has no scores, and adds it to tokens
+/// without candidates as `{}`. The map is also accepted as input.
+#[derive(Debug, Default)]
+pub struct JsonlFormat {
+    /// The token objects of each sentence that has been read but not written,
+    /// and whether blank lines came before it.
+    sentences: VecDeque<(bool, Vec<JsonToken>)>,
+    line_number: usize,
+    document_breaks: usize,
+    /// Whether blank lines have been read since the last sentence.
+    after_blank: bool,
+}
+
+impl JsonlFormat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn error(&self, line: &str, message: impl ToString) -> ParseError {
+        ParseError::new(
+            self.line_number,
+            1,
+            line,
+            ParseErrorKind::Other(message.to_string()),
+        )
+    }
+
+    fn parse_sentence(&self, line: &str) -> Result<(Vec<LemmaToken>, Vec<JsonToken>), ParseError> {
+        let tokens: Vec<JsonToken> =
+            serde_json::from_str(line).map_err(|err| self.error(line, err))?;
+        let mut lts = Vec::with_capacity(tokens.len());
+        for (i, token) in tokens.iter().enumerate() {
+            let strings = |key: &str| -> Result<Vec<String>, ParseError> {
+                match token.get(key) {
+                    None | Some(Value::Null) => Ok(Vec::new()),
+                    Some(Value::Object(map)) => Ok(map.keys().cloned().collect()),
+                    Some(Value::Array(values)) => values
+                        .iter()
+                        .map(|v| match v {
+                            Value::String(s) => Ok(s.clone()),
+                            _ => Err(self.error(line, format!("'{}' must hold strings", key))),
+                        })
+                        .collect(),
+                    Some(_) => Err(self.error(line, format!("'{}' must be an array", key))),
+                }
+            };
+            let word = match token.get("word") {
+                Some(Value::String(word)) => word.clone(),
+                _ => return Err(self.error(line, format!("token {} has no 'word'", i + 1))),
+            };
+            let lemgrams = strings("lemgrams")?;
+            let mut lemmas = strings("lemmas")?;
+            if lemmas.is_empty() {
+                for lemgram in &lemgrams {
+                    let lemma = lemgram_to_lemma(lemgram);
+                    if !lemmas.iter().any(|l| l == lemma) {
+                        lemmas.push(lemma.to_string());
+                    }
+                }
+            }
+            lts.push(LemmaToken::new(
+                (i + 1).to_string(),
+                word,
+                lemgrams,
+                lemmas,
+                strings("senses")?,
+            ));
+        }
+        Ok((lts, tokens))
+    }
+}
+
+impl SourceFormat for JsonlFormat {
+    fn read_sentence(
+        &mut self,
+        reader: &mut dyn BufRead,
+    ) -> Result<Option<Vec<LemmaToken>>, CorpusError> {
+        let mut buf = String::new();
+        loop {
+            buf.clear();
+            if reader.read_line(&mut buf)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            let line = buf.trim();
+            if line.is_empty() {
+                if !self.after_blank {
+                    self.document_breaks += 1;
+                }
+                self.after_blank = true;
+                continue;
+            }
+            let (lts, tokens) = self.parse_sentence(line)?;
+            self.sentences
+                .push_back((std::mem::take(&mut self.after_blank), tokens));
+            return Ok(Some(lts));
+        }
+    }
+
+    fn document_breaks(&self) -> usize {
+        self.document_breaks
+    }
+
+    fn output(&mut self) -> &mut dyn OutputFormat {
+        self
+    }
+}
+
+impl OutputFormat for JsonlFormat {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        (lts, scores): &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        let (after_blank, tokens) = self.sentences.pop_front().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "more sentences written than read",
+            )
+        })?;
+        if after_blank {
+            // keep the documents apart
+            writeln!(out)?;
+        }
+        let tokens: Vec<JsonToken> = tokens
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut fields)| {
+                let senses = lts.get(i).map_or(&[][..], |lt| lt.possible_senses());
+                let scores = scores.get(i).and_then(Option::as_deref);
+                fields.insert("senses".into(), senses_map(senses, scores));
+                fields
+            })
+            .collect();
+        serde_json::to_writer(&mut *out, &tokens)?;
+        writeln!(out)
+    }
+
+    fn skip_sentence(&mut self) {
+        self.sentences.pop_front();
+    }
+}
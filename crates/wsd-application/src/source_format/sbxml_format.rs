@@ -0,0 +1,342 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, Read},
+};
+
+use process_corpus::{CorpusError, LemmaToken, ParseError, ParseErrorKind};
+use quick_xml::{
+    Reader,
+    events::{BytesStart, Event},
+};
+
+use super::{SourceFormat, lemgram_to_lemma, strip_score};
+use crate::output_format::{OutputFormat, format_scored_senses};
+use crate::wsd_application::DisambiguatedSentence;
+
+/// Sparv's XML export, with `<sentence>` elements holding `<w>` or `<token>` elements.
+///
+/// Lemgrams are read from the `lex` attribute and candidate senses from the
+/// `sense` attribute, or `saldo` in older exports. Sparv's `:score` suffixes
+/// are ignored. Each `<text>` element is a document. The writer copies the input verbatim and only replaces the
+/// sense attribute of each token with the scored senses.
+#[derive(Debug, Default)]
+pub struct SbXmlFormat {
+    /// The markup of the sentence being read.
+    pending: Vec<Piece>,
+    /// The markup of each sentence that has been read but not written, up to its end tag.
+    sentences: VecDeque<Vec<Piece>>,
+    line_number: usize,
+    document_breaks: usize,
+}
+
+#[derive(Debug)]
+enum Piece {
+    Raw(Vec<u8>),
+    /// The value of the sense attribute of the token at this position in the sentence.
+    Senses {
+        token: usize,
+        original: Vec<u8>,
+    },
+}
+
+const SENTENCE: &[u8] = b"sentence";
+const TEXT: &[u8] = b"text";
+const SENSE_ATTRIBUTES: [&[u8]; 2] = [b"sense", b"saldo"];
+
+impl SbXmlFormat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push_raw(&mut self, raw: &[u8]) {
+        self.line_number += raw.iter().filter(|b| **b == b'\n').count();
+        match self.pending.last_mut() {
+            Some(Piece::Raw(buf)) => buf.extend_from_slice(raw),
+            _ => self.pending.push(Piece::Raw(raw.to_vec())),
+        }
+    }
+
+    fn error(&self, message: impl ToString, raw: &[u8]) -> CorpusError {
+        let line = String::from_utf8_lossy(raw);
+        ParseError::new(
+            self.line_number + 1,
+            1,
+            line.trim(),
+            ParseErrorKind::Other(message.to_string()),
+        )
+        .into()
+    }
+
+    /// Record the markup of a token's start tag, splitting out its sense attribute.
+    fn push_token_tag(
+        &mut self,
+        e: &BytesStart,
+        raw: &[u8],
+        token: usize,
+    ) -> Result<TokenAttributes, CorpusError> {
+        // The tag content is everything between `<` and `>` or `/>`.
+        let content: &[u8] = e;
+        let end = raw.len() - if raw.ends_with(b"/>") { 2 } else { 1 };
+        let start = end - content.len();
+        let mut lemgrams = Vec::new();
+        let mut senses = Vec::new();
+        let mut pos = None;
+        let mut sense_value = None;
+        for attr in e.attributes().with_checks(false) {
+            let attr = attr.map_err(|err| self.error(err, raw))?;
+            let value = attr.unescape_value().map_err(|err| self.error(err, raw))?;
+            match attr.key.as_ref() {
+                b"lex" => lemgrams = split_set(&value),
+                b"pos" => pos = Some(value.to_lowercase()),
+                key if SENSE_ATTRIBUTES.contains(&key) && sense_value.is_none() => {
+                    senses = split_set(&value);
+                    // the raw value borrows from the tag content
+                    let offset = attr.value.as_ptr() as usize - content.as_ptr() as usize;
+                    sense_value = Some(start + offset..start + offset + attr.value.len());
+                }
+                _ => {}
+            }
+        }
+        match sense_value {
+            Some(range) => {
+                self.push_raw(&raw[..range.start]);
+                self.pending.push(Piece::Senses {
+                    token,
+                    original: raw[range.clone()].to_vec(),
+                });
+                self.push_raw(&raw[range.end..]);
+            }
+            None => self.push_raw(raw),
+        }
+        Ok(TokenAttributes {
+            lemgrams,
+            senses,
+            pos,
+        })
+    }
+}
+
+/// The annotations of a token whose text has not been read yet.
+#[derive(Debug)]
+struct TokenAttributes {
+    lemgrams: Vec<String>,
+    senses: Vec<String>,
+    pos: Option<String>,
+}
+
+impl TokenAttributes {
+    fn into_lemma_token(self, position: usize, word: String) -> LemmaToken {
+        let mut lemmas: Vec<String> = Vec::new();
+        for lemgram in &self.lemgrams {
+            let lemma = lemgram_to_lemma(lemgram);
+            if !lemmas.iter().any(|l| l == lemma) {
+                lemmas.push(lemma.to_string());
+            }
+        }
+        if lemmas.is_empty() {
+            // like the tab format, words without a lemgram use `word..pos`
+            if let Some(pos) = &self.pos {
+                lemmas.push(format!("{}..{}", word, pos));
+            }
+        }
+        LemmaToken::new(
+            position.to_string(),
+            word,
+            self.lemgrams,
+            lemmas,
+            self.senses,
+        )
+    }
+}
+
+impl SourceFormat for SbXmlFormat {
+    fn read_sentence(
+        &mut self,
+        reader: &mut dyn BufRead,
+    ) -> Result<Option<Vec<LemmaToken>>, CorpusError> {
+        let mut xml = Reader::from_reader(Recorder {
+            inner: reader,
+            recorded: Vec::new(),
+        });
+        // a call can start in the middle of the document, after the start tags of
+        // the elements it closes
+        xml.config_mut().check_end_names = false;
+        xml.config_mut().allow_unmatched_ends = true;
+        let mut buf = Vec::new();
+        let mut sentence: Option<Vec<LemmaToken>> = None;
+        let mut token: Option<(TokenAttributes, String)> = None;
+        loop {
+            buf.clear();
+            let event = xml.read_event_into(&mut buf);
+            let raw = std::mem::take(&mut xml.get_mut().recorded);
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => return Err(self.error(err, &raw)),
+            };
+            match event {
+                Event::Start(e) if e.name().as_ref() == SENTENCE => {
+                    self.push_raw(&raw);
+                    sentence = Some(Vec::new());
+                }
+                Event::Start(e) | Event::Empty(e) if e.name().as_ref() == TEXT => {
+                    self.push_raw(&raw);
+                    self.document_breaks += 1;
+                }
+                Event::Empty(e) if e.name().as_ref() == SENTENCE => {
+                    self.push_raw(&raw);
+                    self.sentences.push_back(std::mem::take(&mut self.pending));
+                    return Ok(Some(Vec::new()));
+                }
+                Event::End(e) if e.name().as_ref() == SENTENCE => {
+                    self.push_raw(&raw);
+                    if let Some(lts) = sentence.take() {
+                        self.sentences.push_back(std::mem::take(&mut self.pending));
+                        return Ok(Some(lts));
+                    }
+                }
+                Event::Start(e) if is_token(e.name().as_ref()) => match sentence.as_ref() {
+                    Some(lts) => {
+                        let attributes = self.push_token_tag(&e, &raw, lts.len())?;
+                        token = Some((attributes, String::new()));
+                    }
+                    None => self.push_raw(&raw),
+                },
+                Event::Empty(e) if is_token(e.name().as_ref()) => match sentence.as_mut() {
+                    Some(lts) => {
+                        let attributes = self.push_token_tag(&e, &raw, lts.len())?;
+                        lts.push(attributes.into_lemma_token(lts.len() + 1, String::new()));
+                    }
+                    None => self.push_raw(&raw),
+                },
+                Event::End(e) if is_token(e.name().as_ref()) => {
+                    self.push_raw(&raw);
+                    if let (Some((attributes, word)), Some(lts)) = (token.take(), sentence.as_mut())
+                    {
+                        lts.push(attributes.into_lemma_token(lts.len() + 1, word));
+                    }
+                }
+                Event::Text(e) if token.is_some() => {
+                    self.push_raw(&raw);
+                    let text = e.unescape().map_err(|err| self.error(err, &raw))?;
+                    if let Some((_, word)) = token.as_mut() {
+                        word.push_str(&text);
+                    }
+                }
+                Event::CData(e) if token.is_some() => {
+                    self.push_raw(&raw);
+                    if let Some((_, word)) = token.as_mut() {
+                        word.push_str(&String::from_utf8_lossy(&e));
+                    }
+                }
+                Event::Eof if sentence.is_some() => {
+                    return Err(self.error("unexpected end of input inside <sentence>", &raw));
+                }
+                Event::Eof => {
+                    self.push_raw(&raw);
+                    return Ok(None);
+                }
+                _ => self.push_raw(&raw),
+            }
+        }
+    }
+
+    fn document_breaks(&self) -> usize {
+        self.document_breaks
+    }
+
+    fn output(&mut self) -> &mut dyn OutputFormat {
+        self
+    }
+}
+
+impl OutputFormat for SbXmlFormat {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        (lts, scores): &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        let pieces = self.sentences.pop_front().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "more sentences written than read",
+            )
+        })?;
+        for piece in pieces {
+            match piece {
+                Piece::Raw(raw) => out.write_all(&raw)?,
+                Piece::Senses { token, original } => match (lts.get(token), scores.get(token)) {
+                    (Some(lt), Some(Some(scores))) => {
+                        let value = format!(
+                            "|{}|",
+                            format_scored_senses(lt.possible_senses(), scores, "|")
+                        );
+                        out.write_all(quick_xml::escape::escape(value).as_bytes())?;
+                    }
+                    _ => out.write_all(&original)?,
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn io::Write) -> io::Result<()> {
+        for piece in self
+            .sentences
+            .drain(..)
+            .flatten()
+            .chain(self.pending.drain(..))
+        {
+            match piece {
+                Piece::Raw(raw) => out.write_all(&raw)?,
+                Piece::Senses { original, .. } => out.write_all(&original)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn skip_sentence(&mut self) {
+        self.sentences.pop_front();
+    }
+}
+
+fn is_token(name: &[u8]) -> bool {
+    matches!(name, b"w" | b"token")
+}
+
+/// Split a Sparv set value like `|a:0.5|b|` and drop the scores.
+fn split_set(value: &str) -> Vec<String> {
+    value
+        .split('|')
+        .filter(|v| !v.is_empty())
+        .map(|v| strip_score(v).to_string())
+        .collect()
+}
+
+/// A reader that keeps a copy of every byte consumed through it.
+struct Recorder<'a> {
+    inner: &'a mut dyn BufRead,
+    recorded: Vec<u8>,
+}
+
+impl Read for Recorder<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for Recorder<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            self.recorded.extend_from_slice(&buf[..amt]);
+        }
+        self.inner.consume(amt);
+    }
+}
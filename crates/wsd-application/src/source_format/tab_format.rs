@@ -1,25 +1,369 @@
-use process_corpus::{self, LemmaToken};
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+};
 
-use super::SourceFormat;
+use process_corpus::{
+    CorpusError, LemmaToken, LemmaTokenReader, ParseError, ParseErrorKind, ParseMode,
+    decode_escapes,
+};
 
+use super::{SourceFormat, lemgram_to_lemma, strip_score};
+use crate::output_format::format_scored_senses;
+use crate::wsd_application::DisambiguatedSentence;
+use crate::{OutputFormat, TabOutput};
+
+/// The header of the column added for the sense distribution.
+const SCORES_HEADER: &str = "wsd";
+
+/// A column of tab-separated input, by 0-based index or by header name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TabColumn {
+    Index(usize),
+    Name(String),
+}
+
+impl std::str::FromStr for TabColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err("empty column".into());
+        }
+        Ok(s.parse()
+            .map(Self::Index)
+            .unwrap_or_else(|_| Self::Name(s.to_string())))
+    }
+}
+
+/// Where a [`TabFormat`] finds the fields of each token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabColumns {
+    pub word: TabColumn,
+    /// A POS tag or MSD, whose first part is added to lemmas without one,
+    /// `utskott` -> `utskott..nn`.
+    pub pos: Option<TabColumn>,
+    pub lemgrams: Option<TabColumn>,
+    /// Lemmas, or derived from the lemgrams if not given.
+    pub lemmas: Option<TabColumn>,
+    pub senses: Option<TabColumn>,
+    /// Separates the values in multi-valued cells.
+    pub separator: char,
+    /// Whether the first line names the columns. Required for named columns.
+    pub header: bool,
+}
+
+impl TabColumns {
+    /// The layout of [`LemmaToken`]: position, word, affix marker, lemgrams,
+    /// lemmas and senses.
+    pub fn sparv() -> Self {
+        Self {
+            word: TabColumn::Index(1),
+            pos: None,
+            lemgrams: Some(TabColumn::Index(3)),
+            lemmas: Some(TabColumn::Index(4)),
+            senses: Some(TabColumn::Index(5)),
+            separator: '|',
+            header: false,
+        }
+    }
+
+    fn is_sparv(&self) -> bool {
+        *self == Self::sparv()
+    }
+
+    fn columns(&self) -> impl Iterator<Item = &TabColumn> {
+        [
+            Some(&self.word),
+            self.pos.as_ref(),
+            self.lemgrams.as_ref(),
+            self.lemmas.as_ref(),
+            self.senses.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+impl Default for TabColumns {
+    fn default() -> Self {
+        Self::sparv()
+    }
+}
+
+impl std::str::FromStr for TabColumns {
+    type Err = String;
+
+    /// Parse `sparv` or a list like `word:form,pos:msd,lemmas:3,senses:4`.
+    ///
+    /// Naming a column implies a header.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "sparv" {
+            return Ok(Self::sparv());
+        }
+        let mut word = None;
+        let mut columns = Self {
+            word: TabColumn::Index(0),
+            pos: None,
+            lemgrams: None,
+            lemmas: None,
+            senses: None,
+            separator: '|',
+            header: false,
+        };
+        for entry in s.split(',') {
+            let (field, column) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("expected 'field:column', found '{}'", entry))?;
+            let column: TabColumn = column.parse()?;
+            columns.header |= matches!(column, TabColumn::Name(_));
+            match field {
+                "word" => word = Some(column),
+                "pos" | "msd" => columns.pos = Some(column),
+                "lemgrams" => columns.lemgrams = Some(column),
+                "lemmas" => columns.lemmas = Some(column),
+                "senses" => columns.senses = Some(column),
+                _ => return Err(format!("unknown field '{}'", field)),
+            }
+        }
+        columns.word = word.ok_or("no word column")?;
+        Ok(columns)
+    }
+}
+
+/// The column indices of [`TabColumns`], once the header has been read.
+#[derive(Debug)]
+struct Indices {
+    num_columns: usize,
+    word: usize,
+    pos: Option<usize>,
+    lemgrams: Option<usize>,
+    lemmas: Option<usize>,
+    senses: Option<usize>,
+}
+
+#[derive(Debug)]
+enum Line {
+    Header(String),
+    /// A token line, with the position of the token in its sentence.
+    Token(String, usize),
+}
+
+/// Tab-separated tokens, one per line, with blank lines between sentences.
+///
+/// By default the columns are those of [`LemmaToken`] and the output is that
+/// of [`TabOutput`]. With other [`TabColumns`] the writer keeps each line and
+/// adds the sense distribution, like `anslag..1:0.731|anslag..2:0.269`, as
+/// a new last column.
 #[derive(Debug, Default)]
-pub struct TabFormat {}
+pub struct TabFormat {
+    reader: LemmaTokenReader,
+    mode: ParseMode,
+    columns: TabColumns,
+    indices: Option<Indices>,
+    /// The lines of each sentence that has been read but not written.
+    sentences: VecDeque<Vec<Line>>,
+    line_number: usize,
+    num_malformed: usize,
+    output: TabOutput,
+}
+
+impl TabFormat {
+    pub fn new(mode: ParseMode) -> Self {
+        Self::with_columns(mode, TabColumns::sparv())
+    }
 
-impl TabFormat {}
+    pub fn with_columns(mode: ParseMode, columns: TabColumns) -> Self {
+        Self {
+            reader: LemmaTokenReader::new(mode),
+            mode,
+            columns,
+            ..Self::default()
+        }
+    }
+
+    fn error(&self, line: &str, column: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(self.line_number, column, line, kind)
+    }
+
+    /// Resolve the columns, by name from `header` if given.
+    fn find_indices(&self, header: Option<&str>) -> Result<Indices, ParseError> {
+        let names: Vec<&str> = header.map(|h| h.split('\t').collect()).unwrap_or_default();
+        let find = |column: &TabColumn| match column {
+            TabColumn::Index(ix) => Ok(*ix),
+            TabColumn::Name(name) => names.iter().position(|n| n == name).ok_or_else(|| {
+                let message = match header {
+                    Some(_) => format!("no column named '{}' in the header", name),
+                    None => format!("column '{}' is named but the input has no header", name),
+                };
+                self.error(header.unwrap_or(""), 1, ParseErrorKind::Other(message))
+            }),
+        };
+        let find_opt = |column: &Option<TabColumn>| column.as_ref().map(find).transpose();
+        let mut num_columns = 0;
+        for column in self.columns.columns() {
+            num_columns = num_columns.max(find(column)? + 1);
+        }
+        Ok(Indices {
+            num_columns,
+            word: find(&self.columns.word)?,
+            pos: find_opt(&self.columns.pos)?,
+            lemgrams: find_opt(&self.columns.lemgrams)?,
+            lemmas: find_opt(&self.columns.lemmas)?,
+            senses: find_opt(&self.columns.senses)?,
+        })
+    }
+
+    fn parse_token(
+        &mut self,
+        indices: &Indices,
+        line: &str,
+        position: usize,
+    ) -> Result<LemmaToken, ParseError> {
+        let cols: Vec<&str> = line.split('\t').collect();
+        if cols.len() < indices.num_columns {
+            let err = self.error(
+                line,
+                cols.len() + 1,
+                ParseErrorKind::MissingColumns {
+                    expected: indices.num_columns,
+                    found: cols.len(),
+                },
+            );
+            if self.mode == ParseMode::Strict {
+                return Err(err);
+            }
+            self.num_malformed += 1;
+            log::warn!("{}, repairing line", err);
+        }
+        let column = |ix: Option<usize>| ix.and_then(|ix| cols.get(ix)).copied().unwrap_or("");
+        let values = |ix: Option<usize>| {
+            column(ix)
+                .split(self.columns.separator)
+                .filter(|v| !v.is_empty() && *v != "_")
+                .map(strip_score)
+        };
+        let lemgrams: Vec<String> = values(indices.lemgrams).map(String::from).collect();
+        let mut lemmas: Vec<String> = Vec::new();
+        let candidates: Vec<&str> = match indices.lemmas {
+            Some(_) => values(indices.lemmas).collect(),
+            None => lemgrams.iter().map(|l| lemgram_to_lemma(l)).collect(),
+        };
+        // the context model knows lemmas as `lemma..pos` with lowercase SUC tags
+        let pos = column(indices.pos)
+            .split(['.', ' ', self.columns.separator])
+            .next()
+            .filter(|pos| !pos.is_empty() && *pos != "_")
+            .map(str::to_lowercase);
+        for lemma in candidates {
+            let lemma = match &pos {
+                Some(pos) if !lemma.contains("..") => format!("{}..{}", lemma, pos),
+                _ => lemma.to_string(),
+            };
+            if !lemmas.contains(&lemma) {
+                lemmas.push(lemma);
+            }
+        }
+        let senses = values(indices.senses).map(String::from).collect();
+        Ok(LemmaToken::new(
+            position.to_string(),
+            column(Some(indices.word)),
+            lemgrams,
+            lemmas,
+            senses,
+        ))
+    }
+}
 
 impl SourceFormat for TabFormat {
-    fn read_sentences(
-        &self,
-        reader: &mut dyn std::io::BufRead,
-        batch_size: usize,
-    ) -> std::io::Result<Vec<Vec<LemmaToken>>> {
-        let mut out = Vec::new();
-        while out.len() < batch_size {
-            match process_corpus::read_lemma_tokens(reader)? {
-                Some(lts) => out.push(lts),
-                None => return Ok(out),
+    fn read_sentence(
+        &mut self,
+        reader: &mut dyn BufRead,
+    ) -> Result<Option<Vec<LemmaToken>>, CorpusError> {
+        if self.columns.is_sparv() {
+            return self.reader.read_sentence(reader);
+        }
+        let mut lts = Vec::new();
+        let mut lines = Vec::new();
+        let mut buf = String::new();
+        loop {
+            buf.clear();
+            if reader.read_line(&mut buf)? == 0 {
+                break;
+            }
+            self.line_number += 1;
+            let text = decode_escapes(buf.trim_end_matches(['\n', '\r'])).into_owned();
+            if text.trim().is_empty() {
+                if lts.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            let indices = match self.indices.take() {
+                Some(indices) => indices,
+                None if self.columns.header => {
+                    self.indices = Some(self.find_indices(Some(&text))?);
+                    lines.push(Line::Header(format!("{}\t{}", text, SCORES_HEADER)));
+                    continue;
+                }
+                None => self.find_indices(None)?,
             };
+            let lt = self.parse_token(&indices, &text, lts.len() + 1);
+            self.indices = Some(indices);
+            lts.push(lt?);
+            lines.push(Line::Token(text, lts.len() - 1));
+        }
+        if lts.is_empty() {
+            return Ok(None);
         }
-        Ok(out)
+        self.sentences.push_back(lines);
+        Ok(Some(lts))
+    }
+
+    fn num_malformed(&self) -> usize {
+        self.reader.num_malformed() + self.num_malformed
+    }
+
+    fn output(&mut self) -> &mut dyn OutputFormat {
+        if self.columns.is_sparv() {
+            &mut self.output
+        } else {
+            self
+        }
+    }
+}
+
+impl OutputFormat for TabFormat {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        (lts, scores): &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        let lines = self.sentences.pop_front().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "more sentences written than read",
+            )
+        })?;
+        let separator = self.columns.separator.to_string();
+        for line in lines {
+            match line {
+                Line::Header(text) => writeln!(out, "{}", text)?,
+                Line::Token(text, i) => match (lts.get(i), scores.get(i)) {
+                    (Some(lt), Some(Some(scores))) => writeln!(
+                        out,
+                        "{}\t{}",
+                        text,
+                        format_scored_senses(lt.possible_senses(), scores, &separator)
+                    )?,
+                    _ => writeln!(out, "{}\t_", text)?,
+                },
+            }
+        }
+        writeln!(out)
+    }
+
+    fn skip_sentence(&mut self) {
+        self.sentences.pop_front();
     }
 }
@@ -0,0 +1,279 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+};
+
+use process_corpus::{CorpusError, LemmaToken, ParseError, ParseErrorKind};
+
+use super::{SourceFormat, lemgram_to_lemma, strip_score};
+use crate::output_format::{OutputFormat, format_scored_senses};
+use crate::wsd_application::DisambiguatedSentence;
+
+const HEADER_START: &str = "<!-- #vrt positional-attributes:";
+const HEADER_END: &str = "-->";
+
+/// The positional attributes a [`VrtFormat`] reads and writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VrtAttributes {
+    /// The positional attributes in column order, unless the input declares
+    /// them in a `<!-- #vrt positional-attributes: ... -->` header.
+    pub positional: Vec<String>,
+    pub word: String,
+    /// Lemmas or lemgrams; a lemgram like `utskott..nn.1` gives the lemma `utskott..nn`.
+    pub lemma: String,
+    pub sense: String,
+    /// The attribute added for the sense distribution.
+    pub scores: String,
+}
+
+impl Default for VrtAttributes {
+    fn default() -> Self {
+        Self {
+            positional: ["word", "pos", "lemma", "lex", "sense"]
+                .map(String::from)
+                .to_vec(),
+            word: "word".into(),
+            lemma: "lex".into(),
+            sense: "sense".into(),
+            scores: "wsd".into(),
+        }
+    }
+}
+
+/// Korp's verticalized text: structural tags on lines of their own around
+/// tab-separated token lines.
+///
+/// Tokens inside `<sentence>` elements are disambiguated, and each `<text>`
+/// element is a document. The writer keeps
+/// every line and adds the sense distribution, like `|anslag..1:0.731|anslag..2:0.269|`,
+/// as a new positional attribute after the last column.
+#[derive(Debug)]
+pub struct VrtFormat {
+    attributes: VrtAttributes,
+    columns: Columns,
+    /// The lines of the sentence being read.
+    pending: Vec<Line>,
+    /// The lines of each sentence that has been read but not written.
+    sentences: VecDeque<Vec<Line>>,
+    line_number: usize,
+    document_breaks: usize,
+}
+
+#[derive(Debug, Default)]
+struct Columns {
+    num_columns: usize,
+    word: Option<usize>,
+    lemma: Option<usize>,
+    sense: Option<usize>,
+}
+
+#[derive(Debug)]
+enum Line {
+    Markup(String),
+    /// A token line, with the position of the token in its sentence.
+    Token(String, Option<usize>),
+}
+
+impl Default for VrtFormat {
+    fn default() -> Self {
+        Self::new(VrtAttributes::default())
+    }
+}
+
+impl VrtFormat {
+    pub fn new(attributes: VrtAttributes) -> Self {
+        let mut format = Self {
+            attributes,
+            columns: Columns::default(),
+            pending: Vec::new(),
+            sentences: VecDeque::new(),
+            line_number: 0,
+            document_breaks: 0,
+        };
+        format.columns = format.find_columns(&format.attributes.positional);
+        format
+    }
+
+    fn find_columns(&self, positional: &[String]) -> Columns {
+        let find = |name: &str| positional.iter().position(|a| a == name);
+        Columns {
+            num_columns: positional.len(),
+            word: find(&self.attributes.word),
+            lemma: find(&self.attributes.lemma),
+            sense: find(&self.attributes.sense),
+        }
+    }
+
+    /// Take the attribute names from a header and declare the scores attribute in it.
+    fn read_header(&mut self, line: &str) -> Option<String> {
+        let names = line
+            .trim()
+            .strip_prefix(HEADER_START)?
+            .strip_suffix(HEADER_END)?;
+        let positional: Vec<String> = names.split_whitespace().map(String::from).collect();
+        self.columns = self.find_columns(&positional);
+        Some(format!(
+            "{} {} {} {}",
+            HEADER_START,
+            positional.join(" "),
+            self.attributes.scores,
+            HEADER_END
+        ))
+    }
+
+    fn parse_token(&self, line: &str, position: usize) -> Result<LemmaToken, ParseError> {
+        let cols: Vec<&str> = line.split('\t').collect();
+        if cols.len() < self.columns.num_columns {
+            return Err(ParseError::new(
+                self.line_number,
+                cols.len() + 1,
+                line,
+                ParseErrorKind::MissingColumns {
+                    expected: self.columns.num_columns,
+                    found: cols.len(),
+                },
+            ));
+        }
+        let column = |ix: Option<usize>| ix.and_then(|ix| cols.get(ix)).copied().unwrap_or("");
+        let mut lemgrams = Vec::new();
+        let mut lemmas: Vec<String> = Vec::new();
+        for value in split_set(column(self.columns.lemma)) {
+            let lemma = lemgram_to_lemma(value);
+            if lemma != value {
+                lemgrams.push(value.to_string());
+            }
+            if !lemmas.iter().any(|l| l == lemma) {
+                lemmas.push(lemma.to_string());
+            }
+        }
+        let senses = split_set(column(self.columns.sense))
+            .map(String::from)
+            .collect();
+        Ok(LemmaToken::new(
+            position.to_string(),
+            column(self.columns.word),
+            lemgrams,
+            lemmas,
+            senses,
+        ))
+    }
+}
+
+impl SourceFormat for VrtFormat {
+    fn read_sentence(
+        &mut self,
+        reader: &mut dyn BufRead,
+    ) -> Result<Option<Vec<LemmaToken>>, CorpusError> {
+        let mut sentence: Option<Vec<LemmaToken>> = None;
+        let mut buf = String::new();
+        loop {
+            buf.clear();
+            if reader.read_line(&mut buf)? == 0 {
+                if sentence.is_some() {
+                    // the last sentence is not closed
+                    self.sentences.push_back(std::mem::take(&mut self.pending));
+                }
+                return Ok(sentence);
+            }
+            self.line_number += 1;
+            let text = buf.trim_end_matches(['\n', '\r']).to_string();
+            if !text.starts_with('<') {
+                let token = match sentence.as_mut() {
+                    Some(lts) => {
+                        lts.push(self.parse_token(&text, lts.len() + 1)?);
+                        Some(lts.len() - 1)
+                    }
+                    None => None,
+                };
+                self.pending.push(Line::Token(text, token));
+                continue;
+            }
+            let text = self.read_header(&text).unwrap_or(text);
+            let is_end = text.trim_end() == "</sentence>";
+            if text.starts_with("<sentence") && !is_end {
+                sentence = Some(Vec::new());
+            } else if is_start_tag(&text, "text") {
+                self.document_breaks += 1;
+            }
+            self.pending.push(Line::Markup(text));
+            if is_end && sentence.is_some() {
+                self.sentences.push_back(std::mem::take(&mut self.pending));
+                return Ok(sentence);
+            }
+        }
+    }
+
+    fn document_breaks(&self) -> usize {
+        self.document_breaks
+    }
+
+    fn output(&mut self) -> &mut dyn OutputFormat {
+        self
+    }
+}
+
+impl OutputFormat for VrtFormat {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn io::Write,
+        (lts, scores): &DisambiguatedSentence,
+    ) -> io::Result<()> {
+        let lines = self.sentences.pop_front().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "more sentences written than read",
+            )
+        })?;
+        for line in lines {
+            match line {
+                Line::Markup(text) => writeln!(out, "{}", text)?,
+                Line::Token(text, token) => {
+                    match token.and_then(|i| Some((lts.get(i)?, scores.get(i)?.as_ref()?))) {
+                        Some((lt, scores)) => writeln!(
+                            out,
+                            "{}\t|{}|",
+                            text,
+                            format_scored_senses(lt.possible_senses(), scores, "|")
+                        )?,
+                        None => writeln!(out, "{}\t|", text)?,
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn io::Write) -> io::Result<()> {
+        for line in self
+            .sentences
+            .drain(..)
+            .flatten()
+            .chain(self.pending.drain(..))
+        {
+            match line {
+                Line::Markup(text) => writeln!(out, "{}", text)?,
+                Line::Token(text, _) => writeln!(out, "{}\t|", text)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn skip_sentence(&mut self) {
+        self.sentences.pop_front();
+    }
+}
+
+/// Whether `line` is a start tag of `name`, like `<text title="...">`.
+fn is_start_tag(line: &str, name: &str) -> bool {
+    line.strip_prefix('<')
+        .and_then(|rest| rest.strip_prefix(name))
+        .is_some_and(|rest| rest.starts_with(['>', ' ', '\t']))
+}
+
+/// Split a Korp set value like `|a:0.5|b|`, or a single value, and drop the scores.
+fn split_set(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split('|')
+        .filter(|v| !v.is_empty() && *v != "_")
+        .map(strip_score)
+}
@@ -1,64 +1,593 @@
+use std::{
+    fs, io,
+    ops::Range,
+    sync::{Arc, Mutex, OnceLock},
+};
+
 use hashbrown::HashMap;
-use ndarray::Array1;
-use w2v::word2vec2;
+use ndarray::{Array1, Array2, CowArray, Ix1};
+use process_corpus::LemmaToken;
+use saldo::SaldoLexicon;
+use w2v::{Embeddings, ExactIndex, LshIndex, MmapEmbeddings, NearestNeighbours, word2vec2};
 
-use crate::{UsageError, WSDApplication};
+use crate::baselines::sense_number;
+use crate::{
+    DisambiguatedSentence, DocumentBag, Kernel, LemmaIdf, ModelBundle, ModelReport, SenseBackoff,
+    TextContext, UsageError, WSDApplication,
+};
 
-pub struct VectorWSD {
-    decay: bool,
-    s1prior: f32,
-    context_width: usize,
-    id_to_vectors: HashMap<String, Array1<f32>>,
-    form_to_ctx_vec: HashMap<String, Array1<f32>>,
+/// The mean context vector of a document, if any of its lemmas has one.
+type DocumentVector = Arc<Option<Array1<f32>>>;
+
+/// Boxed embedding store used for the sense and context vectors.
+pub type SharedEmbeddings = Box<dyn Embeddings + Send + Sync>;
+
+/// The SALDO neighbours of a sense without a vector, and the vector
+/// approximated from them once it is first needed.
+struct Approximation {
+    neighbours: Vec<(String, f32)>,
+    vector: OnceLock<Array1<f32>>,
 }
 
-impl VectorWSD {
-    pub fn new_as_shared(
-        argv: &[String],
-    ) -> Result<crate::SharedWSDApplication, crate::UsageError> {
-        let mut decay = bool::default();
-        let mut s1prior = f32::default();
-        let mut context_width = usize::default();
-        let mut id_to_vectors = None;
-        let mut form_to_ctx_vec = None;
+/// The vectors of the possible senses of a token, `None` for senses without one.
+type SenseVectors<'a> = Vec<Option<CowArray<'a, f32, Ix1>>>;
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct VectorWSDOptions {
+    pub decay: bool,
+    pub s1prior: f32,
+    pub context_width: usize,
+    /// The distance weighting, or the one of `decay` if not given.
+    pub kernel: Option<Kernel>,
+    /// The number of context words before the target, if not `context_width`.
+    pub left_width: Option<usize>,
+    /// The number of context words after the target, if not `context_width`.
+    pub right_width: Option<usize>,
+    /// The number of sentences on each side whose tokens are also context,
+    /// within the window of the target.
+    pub context_sentences: usize,
+    /// The weight of the mean context vector of the whole document, or 0 to
+    /// not use it.
+    pub document_weight: f32,
+}
 
+impl VectorWSDOptions {
+    /// Override the options given as `-decay=`, `-s1Prior=`, `-contextWidth=`,
+    /// `-kernel=`, `-leftWidth=`, `-rightWidth=`, `-contextSentences=` and
+    /// `-documentWeight=` in `argv`.
+    pub fn update_from_args(&mut self, argv: &[String]) -> Result<(), UsageError> {
         for a in argv {
             if let Some(val) = a.strip_prefix("-decay=") {
-                decay = val.parse().map_err(|_err| UsageError::BadValue {
+                self.decay = val.parse().map_err(|_err| UsageError::BadValue {
                     param: "-decay=".into(),
                     value: val.to_string(),
                 })?;
             } else if let Some(val) = a.strip_prefix("-s1Prior=") {
-                s1prior = val.parse().map_err(|_err| UsageError::BadValue {
+                self.s1prior = val.parse().map_err(|_err| UsageError::BadValue {
                     param: "-s1Prior=".into(),
                     value: val.to_string(),
                 })?;
             } else if let Some(val) = a.strip_prefix("-contextWidth=") {
-                context_width = val.parse().map_err(|_err| UsageError::BadValue {
+                self.context_width = val.parse().map_err(|_err| UsageError::BadValue {
                     param: "-contextWidth=".into(),
                     value: val.to_string(),
                 })?;
-            } else if let Some(val) = a.strip_prefix("-svFile=") {
-                id_to_vectors =
-                    Some(read_sense_vectors(val).map_err(|err| err.with_param("-svFile="))?);
-            } else if let Some(val) = a.strip_prefix("-cvFile=") {
-                form_to_ctx_vec =
-                    Some(read_ctx_vectors(val).map_err(|err| err.with_param("-cvFile="))?);
+            } else if let Some(val) = a.strip_prefix("-kernel=") {
+                self.kernel = Some(val.parse()?);
+            } else if let Some(val) = a.strip_prefix("-leftWidth=") {
+                self.left_width = Some(val.parse().map_err(|_err| UsageError::BadValue {
+                    param: "-leftWidth=".into(),
+                    value: val.to_string(),
+                })?);
+            } else if let Some(val) = a.strip_prefix("-rightWidth=") {
+                self.right_width = Some(val.parse().map_err(|_err| UsageError::BadValue {
+                    param: "-rightWidth=".into(),
+                    value: val.to_string(),
+                })?);
+            } else if let Some(val) = a.strip_prefix("-contextSentences=") {
+                self.context_sentences = val.parse().map_err(|_err| UsageError::BadValue {
+                    param: "-contextSentences=".into(),
+                    value: val.to_string(),
+                })?;
+            } else if let Some(val) = a.strip_prefix("-documentWeight=") {
+                self.document_weight = val.parse().map_err(|_err| UsageError::BadValue {
+                    param: "-documentWeight=".into(),
+                    value: val.to_string(),
+                })?;
             }
         }
-        Ok(Box::new(Self {
-            // saldo,
+        Ok(())
+    }
+}
+
+/// How the sense and context models are held in memory, chosen with `-vectorStore=`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum VectorStore {
+    /// One `HashMap` entry per word.
+    #[default]
+    Map,
+    /// All vectors in one contiguous matrix.
+    Matrix,
+    /// Vectors decoded on demand from a memory-mapped file.
+    Mmap,
+}
+
+impl std::str::FromStr for VectorStore {
+    type Err = UsageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "map" => Ok(Self::Map),
+            "matrix" => Ok(Self::Matrix),
+            "mmap" => Ok(Self::Mmap),
+            _ => Err(UsageError::BadValue {
+                param: "-vectorStore=".into(),
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+pub struct VectorWSD {
+    s1prior: f32,
+    /// The weights of the context words at distance 1, 2, .. before the target.
+    left_weights: Vec<f32>,
+    /// The weights of the context words at distance 1, 2, .. after the target.
+    right_weights: Vec<f32>,
+    idf: Option<LemmaIdf>,
+    context_sentences: usize,
+    document_weight: f32,
+    id_to_vectors: SharedEmbeddings,
+    form_to_ctx_vec: SharedEmbeddings,
+    /// The weighted SALDO neighbours of the senses without a vector.
+    backoff: HashMap<String, Approximation>,
+    /// The mean context vector of the last document, by [`DocumentBag::id`].
+    last_document: Mutex<Option<(u64, DocumentVector)>>,
+}
+
+impl VectorWSD {
+    pub fn new(
+        VectorWSDOptions {
             decay,
             s1prior,
             context_width,
-            id_to_vectors: id_to_vectors
-                .ok_or_else(|| UsageError::missing_required_argument("-svFile="))?,
-            form_to_ctx_vec: form_to_ctx_vec
-                .ok_or_else(|| UsageError::missing_required_argument("-cvFile="))?,
-        }))
+            kernel,
+            left_width,
+            right_width,
+            context_sentences,
+            document_weight,
+        }: VectorWSDOptions,
+        id_to_vectors: SharedEmbeddings,
+        form_to_ctx_vec: SharedEmbeddings,
+    ) -> Self {
+        let left = left_width.unwrap_or(context_width);
+        let right = right_width.unwrap_or(context_width);
+        let (left_weights, right_weights) = match kernel {
+            Some(kernel) => kernel.weights(left, right),
+            None => decay_weights(decay, left, right),
+        };
+        Self {
+            s1prior,
+            left_weights,
+            right_weights,
+            idf: None,
+            context_sentences,
+            document_weight,
+            id_to_vectors,
+            form_to_ctx_vec,
+            backoff: HashMap::new(),
+            last_document: Mutex::default(),
+        }
+    }
+
+    /// Weight each context lemma by its inverse document frequency.
+    pub fn with_idf(mut self, idf: LemmaIdf) -> Self {
+        self.idf = Some(idf);
+        self
+    }
+
+    /// Approximate the vectors of the senses in `saldo` that have none from
+    /// their neighbours.
+    pub fn with_sense_backoff(mut self, saldo: &SaldoLexicon, backoff: SenseBackoff) -> Self {
+        self.backoff = backoff
+            .neighbours(saldo, self.id_to_vectors.as_ref())
+            .into_iter()
+            .map(|(sense, neighbours)| {
+                let vector = OnceLock::new();
+                (sense, Approximation { neighbours, vector })
+            })
+            .collect();
+        log::info!(
+            "{} SALDO senses without vectors are approximated from their neighbours",
+            self.backoff.len()
+        );
+        self
+    }
+
+    pub fn new_as_shared(
+        saldo: Option<&SaldoLexicon>,
+        argv: &[String],
+    ) -> Result<crate::SharedWSDApplication, crate::UsageError> {
+        Ok(std::sync::Arc::new(Self::from_args(saldo, argv)?))
+    }
+
+    /// Create from command-line flags, failing if the models do not fit together.
+    ///
+    /// See [`ModelReport::validate`]. `-senseBackoff=` needs the lexicon.
+    pub fn from_args(saldo: Option<&SaldoLexicon>, argv: &[String]) -> Result<Self, UsageError> {
+        let mut backoff = None;
+        for a in argv {
+            if let Some(val) = a.strip_prefix("-senseBackoff=") {
+                backoff = match val {
+                    "false" => None,
+                    _ => Some(val.parse::<SenseBackoff>()?),
+                };
+            }
+        }
+        let mut wsd = Self::from_args_unchecked(argv)?;
+        let report = wsd.check_models(saldo);
+        report.validate()?;
+        if let Some(coverage) = &report.saldo {
+            log::info!(
+                "{}/{} SALDO senses have vectors, {} sense ids unknown to SALDO",
+                coverage.num_saldo_senses_with_vectors,
+                coverage.num_saldo_senses,
+                coverage.unknown_sense_ids.len()
+            );
+        }
+        if let Some(backoff) = backoff {
+            let saldo = saldo.ok_or_else(|| {
+                UsageError::missing_required_argument("-saldo=, required by -senseBackoff=")
+            })?;
+            wsd = wsd.with_sense_backoff(saldo, backoff);
+        }
+        Ok(wsd)
     }
 
-    fn add_s1prior(&self, ps: &[String], out: &mut [f32], svs: &[Option<&Array1<f32>>]) {
+    /// Create from command-line flags without checking the models.
+    ///
+    /// With `-model=BUNDLE`, the model files and hyperparameters are taken from
+    /// the bundle manifest, but explicit flags still take precedence.
+    pub fn from_args_unchecked(argv: &[String]) -> Result<Self, UsageError> {
+        let mut options = VectorWSDOptions::default();
+        let mut store = VectorStore::default();
+        let mut sv_file = None;
+        let mut cv_file = None;
+        let mut idf_file = None;
+
+        if let Some(dir) = argv.iter().find_map(|a| a.strip_prefix("-model=")) {
+            let bundle = ModelBundle::open(dir)?;
+            bundle.verify_checksums()?;
+            let saldo_version = argv.iter().find_map(|a| a.strip_prefix("-saldoVersion="));
+            if saldo_version.is_some() || argv.iter().any(|a| a.starts_with("-saldo=")) {
+                bundle.check_saldo_version(saldo_version)?;
+            }
+            options = bundle.manifest().hyperparameters.into();
+            sv_file = Some(bundle.sense_model_path().display().to_string());
+            cv_file = Some(bundle.context_model_path().display().to_string());
+            idf_file = bundle.idf_path().map(|path| path.display().to_string());
+        }
+
+        options.update_from_args(argv)?;
+        for a in argv {
+            if let Some(val) = a.strip_prefix("-vectorStore=") {
+                store = val.parse()?;
+            } else if let Some(val) = a.strip_prefix("-svFile=") {
+                sv_file = Some(val.to_string());
+            } else if let Some(val) = a.strip_prefix("-cvFile=") {
+                cv_file = Some(val.to_string());
+            } else if let Some(val) = a.strip_prefix("-idfFile=") {
+                idf_file = Some(val.to_string());
+            }
+        }
+        let sv_file = sv_file.ok_or_else(|| UsageError::missing_required_argument("-svFile="))?;
+        let cv_file = cv_file.ok_or_else(|| UsageError::missing_required_argument("-cvFile="))?;
+        // the two models are independent, so read them at the same time
+        let (id_to_vectors, form_to_ctx_vec) = std::thread::scope(|s| {
+            let sense_handle = s.spawn(|| read_sense_vectors(&sv_file, store));
+            let form_to_ctx_vec = read_ctx_vectors(&cv_file, store);
+            let id_to_vectors = sense_handle.join().expect("reading sense vectors panicked");
+            (id_to_vectors, form_to_ctx_vec)
+        });
+        let id_to_vectors = id_to_vectors.map_err(|err| err.with_param("-svFile="))?;
+        let form_to_ctx_vec = form_to_ctx_vec.map_err(|err| err.with_param("-cvFile="))?;
+        let wsd = Self::new(options, id_to_vectors, form_to_ctx_vec);
+        match idf_file {
+            Some(path) => Ok(wsd.with_idf(read_idf(&path)?)),
+            None => Ok(wsd),
+        }
+    }
+
+    pub fn check_models(&self, saldo: Option<&SaldoLexicon>) -> ModelReport {
+        ModelReport::new(self.sense_vectors(), self.context_vectors(), saldo)
+    }
+
+    pub fn sense_vectors(&self) -> &dyn Embeddings {
+        self.id_to_vectors.as_ref()
+    }
+
+    pub fn context_vectors(&self) -> &dyn Embeddings {
+        self.form_to_ctx_vec.as_ref()
+    }
+
+    /// Write the `k` nearest senses and context lemmas of each query, one per line.
+    ///
+    /// A query is either a sense id such as `utskott..2`, or a lemma such as
+    /// `utskott..nn`, which is expanded to all of its senses that have a vector.
+    /// With `approximate`, the neighbours are searched with an [`LshIndex`].
+    pub fn write_neighbours(
+        &self,
+        queries: &[&str],
+        k: usize,
+        approximate: bool,
+        out: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        let (senses, contexts): (Box<dyn NearestNeighbours>, Box<dyn NearestNeighbours>) =
+            if approximate {
+                log::info!("Building approximate neighbour indices...");
+                (
+                    Box::new(LshIndex::build(self.sense_vectors(), 8, 12, 0)),
+                    Box::new(LshIndex::build(self.context_vectors(), 8, 12, 0)),
+                )
+            } else {
+                (
+                    Box::new(ExactIndex::new(self.sense_vectors())),
+                    Box::new(ExactIndex::new(self.context_vectors())),
+                )
+            };
+        for query in queries {
+            let sense_ids = self.senses_for_query(query);
+            if sense_ids.is_empty() {
+                log::warn!("No sense vectors found for '{}'", query);
+            }
+            for sense_id in sense_ids {
+                let Some(sv) = self.id_to_vectors.embedding(&sense_id) else {
+                    continue;
+                };
+                writeln!(out, "# {}", sense_id)?;
+                // ask for one more, since the sense itself is its nearest neighbour
+                for n in senses
+                    .nearest(sv.view(), k + 1)
+                    .into_iter()
+                    .filter(|n| n.word != sense_id)
+                    .take(k)
+                {
+                    writeln!(out, "sense\t{}\t{}", n.word, n.score)?;
+                }
+                for n in contexts.nearest(sv.view(), k) {
+                    writeln!(out, "context\t{}\t{}", n.word, n.score)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn senses_for_query(&self, query: &str) -> Vec<String> {
+        let is_sense_number = |n: &str| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit());
+        let (base, suffix) = query.rsplit_once("..").unwrap_or((query, ""));
+        if is_sense_number(suffix) {
+            return if self.id_to_vectors.contains(query) {
+                vec![query.to_string()]
+            } else {
+                Vec::new()
+            };
+        }
+        let mut sense_ids: Vec<String> = self
+            .id_to_vectors
+            .words()
+            .filter(|w| {
+                w.rsplit_once("..")
+                    .is_some_and(|(b, n)| b == base && is_sense_number(n))
+            })
+            .map(str::to_string)
+            .collect();
+        // by number, so that `x..2` comes before `x..10`
+        sense_ids.sort_by_key(|id| (sense_number(id).unwrap_or(u32::MAX), id.clone()));
+        sense_ids
+    }
+
+    /// The vectors of the possible senses of `li`, or `None` if it has fewer
+    /// than two senses or none of them has a vector.
+    fn target_sense_vectors(&self, li: &LemmaToken) -> Option<SenseVectors<'_>> {
+        if li.possible_senses().len() < 2 {
+            log::trace!("fewer than 2 senses, returning None");
+            return None;
+        }
+        let svs: Vec<_> = li
+            .possible_senses()
+            .iter()
+            .map(|sense| self.sense_vector(sense))
+            .collect();
+        if svs.iter().all(Option::is_none) {
+            log::trace!("Did not found any embeddings for the possible senses. ");
+            return None;
+        }
+        Some(svs)
+    }
+
+    /// The vector of `sense`, or its approximation from its SALDO neighbours.
+    fn sense_vector(&self, sense: &str) -> Option<CowArray<'_, f32, Ix1>> {
+        if let Some(sv) = self.id_to_vectors.embedding(sense) {
+            return Some(sv);
+        }
+        let Approximation { neighbours, vector } = self.backoff.get(sense)?;
+        let sv = vector.get_or_init(|| {
+            log::debug!(
+                "approximating the vector of {} from {:?}",
+                sense,
+                neighbours
+            );
+            let mut sv = Array1::<f32>::zeros(self.id_to_vectors.dim());
+            for (neighbour, weight) in neighbours {
+                if let Some(nv) = self.id_to_vectors.embedding(neighbour) {
+                    sv.scaled_add(*weight, &nv);
+                }
+            }
+            sv
+        });
+        Some(CowArray::from(sv.view()))
+    }
+
+    /// Whether `sense` has no vector of its own and is scored with one
+    /// approximated from its SALDO neighbours.
+    pub fn is_approximated(&self, sense: &str) -> bool {
+        self.backoff.contains_key(sense)
+    }
+
+    /// Log the scores of the senses of `li`, with a `~` before those that
+    /// are approximated.
+    fn log_scores(&self, li: &LemmaToken, scores: &[f32]) {
+        if !log::log_enabled!(log::Level::Debug) {
+            return;
+        }
+        let scored: Vec<String> = li
+            .possible_senses()
+            .iter()
+            .zip(scores)
+            .map(|(sense, score)| {
+                let approx = if self.is_approximated(sense) { "~" } else { "" };
+                format!("{}{}={}", approx, sense, score)
+            })
+            .collect();
+        log::debug!("Scores of {}: {}", li.word(), scored.join(" "));
+    }
+
+    /// The positions of the context of token `i` in a sentence of `len` tokens.
+    fn window(&self, i: usize, len: usize) -> impl Iterator<Item = usize> {
+        let start = i.saturating_sub(self.left_weights.len());
+        let end = (len - 1).min(i.saturating_add(self.right_weights.len()));
+        (start..=end).filter(move |&k| k != i)
+    }
+
+    /// The weight of the context token `k` for the target `i`.
+    fn weight(&self, i: usize, k: usize) -> f32 {
+        if k < i {
+            self.left_weights[i - k - 1]
+        } else {
+            self.right_weights[k - i - 1]
+        }
+    }
+
+    /// The inverse document frequency of a context lemma, or 1.
+    fn idf(&self, lemma: &str) -> f32 {
+        self.idf.as_ref().map_or(1.0, |idf| idf.get(lemma))
+    }
+
+    /// The mean context vector of the lemmas of a document.
+    fn document_vector(&self, bag: &DocumentBag) -> Option<Array1<f32>> {
+        let mut sum = Array1::<f32>::zeros(self.form_to_ctx_vec.dim());
+        let mut count = 0;
+        for (lemma, n) in bag.lemmas() {
+            if let Some(cv) = self.form_to_ctx_vec.embedding(lemma) {
+                sum.scaled_add(n as f32 * self.idf(lemma), &cv);
+                count += n;
+            }
+        }
+        (count > 0).then(|| sum / count as f32)
+    }
+
+    /// The [`Self::document_vector`] of `bag`, computed once per document.
+    fn cached_document_vector(&self, bag: &DocumentBag) -> DocumentVector {
+        let mut cache = self
+            .last_document
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        match &*cache {
+            Some((id, vector)) if *id == bag.id() => Arc::clone(vector),
+            _ => {
+                let vector = Arc::new(self.document_vector(bag));
+                *cache = Some((bag.id(), Arc::clone(&vector)));
+                vector
+            }
+        }
+    }
+
+    /// Score the tokens at `targets` in `tokens`, or only those that are
+    /// `wanted` if given, whose context may extend past the targets, and add
+    /// `document` to every context.
+    ///
+    /// The context vectors are looked up once, the weighted context sums of
+    /// all targets are one matrix product, and the senses of each target are
+    /// scored with one matrix-vector product.
+    fn score(
+        &self,
+        tokens: &[&LemmaToken],
+        targets: Range<usize>,
+        wanted: Option<&[bool]>,
+        document: Option<&Array1<f32>>,
+    ) -> Vec<Option<Vec<f32>>> {
+        let mut disamb = vec![None; targets.len()];
+        let offset = targets.start;
+        let targets: Vec<(usize, SenseVectors<'_>)> = targets
+            .filter(|i| wanted.is_none_or(|wanted| wanted[i - offset]))
+            .filter_map(|i| Some((i, self.target_sense_vectors(tokens[i])?)))
+            .collect();
+        let (Some((first, _)), Some((last, _))) = (targets.first(), targets.last()) else {
+            return disamb;
+        };
+
+        // only the tokens within the windows of the targets are looked up
+        let span = first.saturating_sub(self.left_weights.len())
+            ..(last.saturating_add(self.right_weights.len()) + 1).min(tokens.len());
+        let dim = self.form_to_ctx_vec.dim();
+        let mut rows = Vec::with_capacity(span.len());
+        let mut cvs = Vec::new();
+        for lk in &tokens[span.clone()] {
+            match lk
+                .possible_lemmas()
+                .first()
+                .and_then(|l| Some((l, self.form_to_ctx_vec.embedding(l)?)))
+            {
+                Some(cv) => {
+                    rows.push(Some(cvs.len()));
+                    cvs.push(cv);
+                }
+                None => rows.push(None),
+            }
+        }
+        let mut contexts = Array2::<f32>::zeros((cvs.len(), dim));
+        for (mut row, (lemma, cv)) in contexts.rows_mut().into_iter().zip(&cvs) {
+            row.assign(cv);
+            if self.idf.is_some() {
+                row *= self.idf(lemma);
+            }
+        }
+        let mut weights = Array2::<f32>::zeros((targets.len(), cvs.len()));
+        for (t, (i, _)) in targets.iter().enumerate() {
+            for k in self.window(*i, tokens.len()) {
+                if let Some(r) = rows[k - span.start] {
+                    weights[[t, r]] = self.weight(*i, k);
+                }
+            }
+        }
+        let mut context_sums = weights.dot(&contexts);
+        if let Some(document) = document {
+            for mut context_sum in context_sums.rows_mut() {
+                context_sum.scaled_add(self.document_weight, document);
+            }
+        }
+
+        for ((i, svs), context_sum) in targets.iter().zip(context_sums.rows()) {
+            let li = tokens[*i];
+            let mut senses = Array2::<f32>::zeros((svs.len(), dim));
+            for (mut row, sv) in senses.rows_mut().into_iter().zip(svs) {
+                if let Some(sv) = sv {
+                    row.assign(sv);
+                }
+            }
+            let mut out = vec![0f32; svs.len()];
+            self.add_s1prior(li.possible_senses(), &mut out, svs);
+            for (o, score) in out.iter_mut().zip(senses.dot(&context_sum)) {
+                *o += score;
+            }
+            normalize_to_probs(&mut out, svs);
+            self.log_scores(li, &out);
+            disamb[*i - offset] = Some(out);
+        }
+        disamb
+    }
+
+    fn add_s1prior(&self, ps: &[String], out: &mut [f32], svs: &[Option<CowArray<'_, f32, Ix1>>]) {
         let mut min = i32::MAX;
         for i in 0..out.len() {
             if svs[i].is_none() {
@@ -86,68 +615,93 @@ impl VectorWSD {
 }
 
 impl WSDApplication for VectorWSD {
-    fn disambiguate(&self, lts: &[process_corpus::LemmaToken], i: usize) -> Option<Vec<f32>> {
-        let len = lts.len();
+    /// Score all tokens of a sentence at once. The results equal those of
+    /// [`VectorWSD::disambiguate`] up to float rounding.
+    fn disambiguate_sentence(&self, lts: Vec<LemmaToken>) -> DisambiguatedSentence {
+        self.disambiguate_in_context(lts, &TextContext::default())
+    }
+
+    fn context_sentences(&self) -> usize {
+        self.context_sentences
+    }
+
+    fn uses_document(&self) -> bool {
+        self.document_weight != 0.0
+    }
+
+    /// Score the sentence with the context windows extending into the
+    /// sentences around it, and with the document's mean context vector
+    /// added to each context.
+    fn disambiguate_in_context(
+        &self,
+        lts: Vec<LemmaToken>,
+        context: &TextContext<'_>,
+    ) -> DisambiguatedSentence {
+        let tokens: Vec<&LemmaToken> = context
+            .before
+            .iter()
+            .flatten()
+            .chain(&lts)
+            .chain(context.after.iter().flatten())
+            .collect();
+        let start = tokens.len() - lts.len() - context.after.iter().map(Vec::len).sum::<usize>();
+        let document = context
+            .document
+            .filter(|_| self.uses_document())
+            .map(|bag| self.cached_document_vector(bag));
+        let document = document.as_deref().and_then(Option::as_ref);
+        let disamb = self.score(&tokens, start..start + lts.len(), context.targets, document);
+        (lts, disamb)
+    }
+
+    fn disambiguate(&self, lts: &[LemmaToken], i: usize) -> Option<Vec<f32>> {
         let li = &lts[i];
         log::trace!("Lemma token {}: {:?}", i, li);
-        let mut out = vec![0f32; li.possible_senses().len()];
-        if out.len() < 2 {
-            log::trace!("out shorter than 2, returning None, out={:?}", out);
-            return None;
-        }
-        // let mut svs = vec![vec![]; out.len()];
-        let mut svs = Vec::with_capacity(out.len());
-        let mut seen_any = false;
-        // let (svs, seen) = self.id_to_vectors.embedding_batch(li.possible_senses());
-        for poss_sense in li.possible_senses() {
-            let v = self.id_to_vectors.get(poss_sense);
-            if v.is_some() {
-                seen_any = true;
-            }
-            svs.push(v);
-            //     svs[j] = self.id_to_vectors.embedding(&poss_sense);
-        }
-        if !seen_any {
-            log::trace!("Did not found any embeddings for the possible senses. ");
-            return None;
-        }
+        let svs = self.target_sense_vectors(li)?;
+        let mut out = vec![0f32; svs.len()];
         self.add_s1prior(li.possible_senses(), &mut out, &svs);
 
-        let start = 0.max(i - self.context_width);
-        let end = (len - 1).min(i + self.context_width);
-        for k in start..=end {
-            if k == i {
-                continue;
-            }
+        for k in self.window(i, lts.len()) {
             let Some(l) = lts[k].possible_lemmas().first() else {
                 continue;
             };
-            let Some(cv) = self.form_to_ctx_vec.get(l) else {
+            let Some(cv) = self.form_to_ctx_vec.embedding(l) else {
                 continue;
             };
-
-            let weight = if self.decay {
-                let mut weight = (self.context_width - k.abs_diff(i) + 1) as f32;
-                weight /= 2.0 * self.context_width as f32;
-                weight
-            } else {
-                0.5f32 / self.context_width as f32
-            };
-
+            let weight = self.weight(i, k) * self.idf(l);
             for j in 0..out.len() {
-                let Some(vs) = svs[j] else {
+                let Some(vs) = &svs[j] else {
                     continue;
                 };
-                let sc = vs.dot(cv);
+                let sc = vs.dot(&cv);
                 out[j] += weight * sc;
             }
         }
         normalize_to_probs(&mut out, &svs);
+        self.log_scores(li, &out);
         Some(out)
     }
 }
 
-fn normalize_to_probs(out: &mut [f32], svs: &[Option<&Array1<f32>>]) {
+/// The weights of `-decay=`: falling by `1 / (left + right)` per word from
+/// the nearest, or all `1 / (left + right)`.
+fn decay_weights(decay: bool, left: usize, right: usize) -> (Vec<f32>, Vec<f32>) {
+    let total = (left + right) as f32;
+    let side = |width: usize| -> Vec<f32> {
+        (1..=width)
+            .map(|d| {
+                if decay {
+                    (width - d + 1) as f32 / total
+                } else {
+                    1.0 / total
+                }
+            })
+            .collect()
+    };
+    (side(left), side(right))
+}
+
+fn normalize_to_probs(out: &mut [f32], svs: &[Option<CowArray<'_, f32, Ix1>>]) {
     let mut m = f32::NEG_INFINITY;
     for i in 0..out.len() {
         if svs[i].is_none() {
@@ -173,23 +727,41 @@ fn normalize_to_probs(out: &mut [f32], svs: &[Option<&Array1<f32>>]) {
     }
 }
 
-fn read_sense_vectors(path: &str) -> Result<HashMap<String, Array1<f32>>, UsageError> {
+fn read_sense_vectors(path: &str, store: VectorStore) -> Result<SharedEmbeddings, UsageError> {
     log::info!("Reading sense vectors...");
-    read_embeddings_from_path(path)
+    read_embeddings_from_path(path, store)
 }
 
-fn read_ctx_vectors(path: &str) -> Result<HashMap<String, Array1<f32>>, UsageError> {
+fn read_ctx_vectors(path: &str, store: VectorStore) -> Result<SharedEmbeddings, UsageError> {
     log::info!("Reading context vectors...");
-    read_embeddings_from_path(path)
+    read_embeddings_from_path(path, store)
 }
 
-fn read_embeddings_from_path(path: &str) -> Result<HashMap<String, Array1<f32>>, UsageError> {
-    let embeddings =
-        word2vec2::read_w2v_file(path, false).map_err(|source| UsageError::Word2VecError {
-            param: String::new(),
+fn read_idf(path: &str) -> Result<LemmaIdf, UsageError> {
+    log::info!("Reading lemma frequencies...");
+    fs::File::open(path)
+        .and_then(|file| LemmaIdf::read(&mut io::BufReader::new(file)))
+        .map_err(|source| UsageError::IoError {
+            param: "-idfFile=".into(),
             path: path.to_string(),
             source,
-        })?;
+        })
+}
 
-    Ok(embeddings)
+fn read_embeddings_from_path(
+    path: &str,
+    store: VectorStore,
+) -> Result<SharedEmbeddings, UsageError> {
+    let embeddings: std::io::Result<SharedEmbeddings> = match store {
+        VectorStore::Map => word2vec2::read_w2v_file_parallel(path, false)
+            .map(|map: HashMap<String, Array1<f32>>| Box::new(map) as SharedEmbeddings),
+        VectorStore::Matrix => word2vec2::read_w2v_matrix_parallel(path, false)
+            .map(|m| Box::new(m) as SharedEmbeddings),
+        VectorStore::Mmap => MmapEmbeddings::open(path).map(|m| Box::new(m) as SharedEmbeddings),
+    };
+    embeddings.map_err(|source| UsageError::Word2VecError {
+        param: String::new(),
+        path: path.to_string(),
+        source,
+    })
 }
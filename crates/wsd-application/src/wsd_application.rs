@@ -1,25 +1,31 @@
-use std::io;
+use std::{
+    collections::BTreeMap,
+    io,
+    ops::Range,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 
-use process_corpus::{self, LemmaToken};
-use saldo::SaldoLexicon;
+use process_corpus::{self, CorpusError, LemmaToken};
+use rayon::prelude::*;
 
-use crate::SourceFormat;
+use crate::{OutputFormat, SourceFormat};
 
-pub trait WSDApplication {
-    fn disambiguate_text(
-        &self,
-        text: Vec<Vec<LemmaToken>>,
-    ) -> Vec<(Vec<LemmaToken>, Vec<Option<Vec<f32>>>)> {
+/// A sentence together with the sense distribution of each token, if any.
+pub type DisambiguatedSentence = (Vec<LemmaToken>, Vec<Option<Vec<f32>>>);
+
+/// Disambiguates tokens in context. Shared between threads by [`disambiguate_sentences`].
+pub trait WSDApplication: Send + Sync {
+    fn disambiguate_text(&self, text: Vec<Vec<LemmaToken>>) -> Vec<DisambiguatedSentence> {
         let mut out = Vec::with_capacity(text.len());
         for sen in text {
             out.push(self.disambiguate_sentence(sen));
         }
         out
     }
-    fn disambiguate_sentence(
-        &self,
-        lts: Vec<LemmaToken>,
-    ) -> (Vec<LemmaToken>, Vec<Option<Vec<f32>>>) {
+    fn disambiguate_sentence(&self, lts: Vec<LemmaToken>) -> DisambiguatedSentence {
         let mut disamb = Vec::with_capacity(lts.len());
         for i in 0..lts.len() {
             disamb.push(self.disambiguate(&lts, i));
@@ -27,41 +33,96 @@ pub trait WSDApplication {
         (lts, disamb)
     }
     fn disambiguate(&self, lts: &[LemmaToken], i: usize) -> Option<Vec<f32>>;
+
+    /// The number of sentences before and after each sentence that
+    /// [`disambiguate_sentences`] passes as context.
+    fn context_sentences(&self) -> usize {
+        0
+    }
+
+    /// Whether [`disambiguate_sentences`] should pass a [`DocumentBag`] of the
+    /// document, which it then has to read before writing any of it. Only one
+    /// document at a time is held, as the source format separates them.
+    fn uses_document(&self) -> bool {
+        false
+    }
+
+    /// Like [`Self::disambiguate_sentence`], with the text around the sentence,
+    /// scoring only the [`TextContext::targets`].
+    fn disambiguate_in_context(
+        &self,
+        lts: Vec<LemmaToken>,
+        context: &TextContext<'_>,
+    ) -> DisambiguatedSentence {
+        let Some(targets) = context.targets else {
+            return self.disambiguate_sentence(lts);
+        };
+        let disamb = (0..lts.len())
+            .map(|i| targets[i].then(|| self.disambiguate(&lts, i)).flatten())
+            .collect();
+        (lts, disamb)
+    }
 }
 
-pub type SharedWSDApplication = Box<dyn WSDApplication>;
+/// The text around a sentence.
+#[derive(Default, Clone, Copy)]
+pub struct TextContext<'a> {
+    /// Up to [`WSDApplication::context_sentences`] sentences before the sentence.
+    pub before: &'a [Vec<LemmaToken>],
+    /// Up to [`WSDApplication::context_sentences`] sentences after the sentence.
+    pub after: &'a [Vec<LemmaToken>],
+    /// The whole document, if [`WSDApplication::uses_document`].
+    pub document: Option<&'a DocumentBag>,
+    /// Which tokens of the sentence to score, or all if `None`. The others
+    /// get no distribution.
+    pub targets: Option<&'a [bool]>,
+}
 
-pub fn evaluate(_wsd: SharedWSDApplication, _eval_lemmas_file: &str, _eval_key_file: &str) {
-    todo!("evaluate is not yet supported")
+/// The lemmas of a document, counted by the first lemma of each token.
+#[derive(Default)]
+pub struct DocumentBag {
+    lemmas: BTreeMap<String, usize>,
+    id: u64,
 }
 
-pub fn read_sentences(
-    reader: &mut dyn io::BufRead,
-    _saldo: Option<&SaldoLexicon>,
-    sbxml: bool,
-    batch_size: usize,
-    _split_mwes: bool,
-    _split_compounds: bool,
-) -> io::Result<Vec<Vec<LemmaToken>>> {
-    let mut out = Vec::new();
-    while out.len() < batch_size {
-        if sbxml {
-            todo!("sbxml format is not yet supported")
-        } else {
-            let lts = match process_corpus::read_lemma_tokens(reader)? {
-                Some(lts) => lts,
-                None => return Ok(out),
-            };
-            out.push(lts);
+impl DocumentBag {
+    pub fn new(text: &[Vec<LemmaToken>]) -> Self {
+        let mut lemmas = BTreeMap::new();
+        for lt in text.iter().flatten() {
+            if let Some(lemma) = lt.possible_lemmas().first() {
+                *lemmas.entry(lemma.clone()).or_default() += 1;
+            }
+        }
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self {
+            lemmas,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
         }
     }
-    Ok(out)
+
+    /// The lemmas and their counts, in lemma order.
+    pub fn lemmas(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.lemmas.iter().map(|(lemma, &n)| (lemma.as_str(), n))
+    }
+
+    /// An id no other bag has, so that applications can keep what they
+    /// compute from the document until the next one.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
 }
 
+pub type SharedWSDApplication = Arc<dyn WSDApplication>;
+
 #[derive(Debug, Copy, Clone)]
 pub struct DisambiguateOptions {
     pub batch_size: usize,
     pub max_sen: usize,
+    /// Write non-ASCII characters as `\xNN` byte escapes.
+    pub escape_output: bool,
+    /// The number of worker threads, each disambiguating a batch at a time,
+    /// or 0 for one per CPU.
+    pub threads: usize,
 }
 
 impl Default for DisambiguateOptions {
@@ -69,75 +130,246 @@ impl Default for DisambiguateOptions {
         Self {
             batch_size: 1,
             max_sen: u32::MAX as usize,
+            escape_output: false,
+            threads: 1,
         }
     }
 }
+/// Disambiguate the sentences `f` reads and write them back in the same format.
 pub fn disambiguate_sentences(
     wsd: SharedWSDApplication,
     reader: &mut dyn io::BufRead,
     out: &mut dyn io::Write,
-    f: &dyn SourceFormat,
+    f: &mut dyn SourceFormat,
+    opts: DisambiguateOptions,
+) -> Result<(), CorpusError> {
+    disambiguate_sentences_with_output(wsd, reader, out, f, None, opts)
+}
+
+/// Like [`disambiguate_sentences`], but write with `output` instead of the
+/// input format, if it is given.
+pub fn disambiguate_sentences_with_output(
+    wsd: SharedWSDApplication,
+    reader: &mut dyn io::BufRead,
+    out: &mut dyn io::Write,
+    f: &mut dyn SourceFormat,
+    mut output: Option<&mut dyn OutputFormat>,
+    opts: DisambiguateOptions,
+) -> Result<(), CorpusError> {
+    let mut escaped;
+    let out: &mut dyn io::Write = if opts.escape_output {
+        escaped = process_corpus::EscapeWriter::new(out);
+        &mut escaped
+    } else {
+        out
+    };
+    disambiguate_documents(
+        wsd.as_ref(),
+        reader,
+        f,
+        opts,
+        &|_| true,
+        &mut |f, sentence| {
+            match output.as_deref_mut() {
+                Some(output) => {
+                    output.write_sentence(out, &sentence)?;
+                    f.output().skip_sentence();
+                }
+                None => f.output().write_sentence(out, &sentence)?,
+            }
+            Ok(())
+        },
+    )?;
+    match output {
+        Some(output) => output.finish(out)?,
+        None => f.output().finish(out)?,
+    }
+    if f.num_malformed() > 0 {
+        log::warn!("Repaired or skipped {} malformed lines", f.num_malformed());
+    }
+    Ok(())
+}
+
+/// Disambiguate `document`, a whole document, with the context sentences and
+/// the [`DocumentBag`] `wsd` asks for, like [`disambiguate_sentences`] does.
+pub fn disambiguate_document(
+    wsd: &dyn WSDApplication,
+    document: Vec<Vec<LemmaToken>>,
+) -> Vec<DisambiguatedSentence> {
+    let n = wsd.context_sentences();
+    if n == 0 && !wsd.uses_document() {
+        return wsd.disambiguate_text(document);
+    }
+    let bag = wsd.uses_document().then(|| DocumentBag::new(&document));
+    (0..document.len())
+        .map(|i| {
+            let context = TextContext {
+                before: &document[i.saturating_sub(n)..i],
+                after: &document[i + 1..i.saturating_add(n).saturating_add(1).min(document.len())],
+                document: bag.as_ref(),
+                targets: None,
+            };
+            wsd.disambiguate_in_context(document[i].clone(), &context)
+        })
+        .collect()
+}
+
+/// Disambiguate the sentences `f` reads, a document at a time, and pass them
+/// to `sink` in order.
+///
+/// Context never crosses a document boundary. Tokens that are not `wanted`
+/// are only context, and passed to `sink` without scores.
+pub(crate) fn disambiguate_documents(
+    wsd: &dyn WSDApplication,
+    reader: &mut dyn io::BufRead,
+    f: &mut dyn SourceFormat,
     DisambiguateOptions {
         batch_size,
         max_sen,
+        threads,
+        ..
     }: DisambiguateOptions,
-) -> io::Result<()> {
+    wanted: &(dyn Fn(&LemmaToken) -> bool + Sync),
+    sink: &mut dyn FnMut(&mut dyn SourceFormat, DisambiguatedSentence) -> Result<(), CorpusError>,
+) -> Result<(), CorpusError> {
+    let pool = match threads {
+        1 => None,
+        n => Some(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(n)
+                .build()
+                .map_err(io::Error::other)?,
+        ),
+    };
+    let batch_size = batch_size.max(1);
+    let num_workers = pool.as_ref().map_or(1, |pool| pool.current_num_threads());
+    let n = wsd.context_sentences();
+    let uses_document = wsd.uses_document();
+    let disambiguate = |lts: Vec<LemmaToken>, context: &TextContext<'_>| {
+        let targets: Vec<bool> = lts.iter().map(wanted).collect();
+        if !targets.contains(&true) {
+            return unscored(lts);
+        }
+        let context = TextContext {
+            targets: targets.contains(&false).then_some(&targets[..]),
+            ..*context
+        };
+        wsd.disambiguate_in_context(lts, &context)
+    };
     let mut total_sentences = 0;
     let mut next_print = 100000;
+    // sentences of the current document that have been read, of which the
+    // first `num_written` are only kept as context for the next ones
+    let mut window: Vec<Vec<LemmaToken>> = Vec::new();
+    let mut num_written = 0;
+    // the first sentence of the next document
+    let mut next_document: Option<Vec<LemmaToken>> = None;
+    let mut document_breaks = f.document_breaks();
+    let mut at_end = max_sen == 0;
 
-    loop {
-        let text = f.read_sentences(
-            reader,
-            // saldo.as_ref(),
-            // args.sbxml,
-            batch_size,
-            // args.split_mwes,
-            // args.split_compounds,
-        )?;
-        // log::trace!(&text);
-        if text.is_empty() {
-            break;
-        }
-        total_sentences += text.len();
-        if total_sentences > next_print {
-            log::info!("{}", next_print);
-            next_print += 100000;
+    while !at_end || next_document.is_some() {
+        window.extend(next_document.take());
+        let mut document_ended = false;
+        let mut num_read = 0;
+        while num_read < num_workers * batch_size && !at_end && !document_ended {
+            let Some(lts) = f.read_sentence(reader)? else {
+                at_end = true;
+                break;
+            };
+            total_sentences += 1;
+            if total_sentences > next_print {
+                log::info!("{}", next_print);
+                next_print += 100000;
+            }
+            at_end = total_sentences >= max_sen;
+            let breaks = f.document_breaks();
+            if breaks != document_breaks && !window.is_empty() {
+                next_document = Some(lts);
+                document_ended = true;
+            } else {
+                window.push(lts);
+                num_read += 1;
+            }
+            document_breaks = breaks;
         }
 
-        // if !args.for_lemma.is_none() {
-        //     todo!("forLemma not supported yet");
-        // } else {
-        let result = wsd.disambiguate_text(text);
-        for p in result {
-            for i in 0..p.0.len() {
-                write!(out, "{}\t", &p.0[i])?;
-                match &p.1[i] {
-                    None => writeln!(out, "_")?,
-                    Some(scores) => writeln!(out, "{}", join_to_string(scores))?,
-                }
+        let complete = at_end || document_ended;
+        // a sentence is ready once the `n` sentences after it have been read,
+        // or the whole document if it uses the document
+        let ready = if complete {
+            window.len()
+        } else if uses_document {
+            num_written
+        } else {
+            window.len().saturating_sub(n).max(num_written)
+        };
+        let disambiguated = if n == 0 && !uses_document {
+            let mut batches = Vec::new();
+            while !window.is_empty() {
+                let rest = window.split_off(batch_size.min(window.len()));
+                batches.push(std::mem::replace(&mut window, rest));
             }
-            writeln!(out,)?;
+            run_batches(pool.as_ref(), batches, |text| {
+                if text.iter().flatten().all(wanted) {
+                    return wsd.disambiguate_text(text);
+                }
+                let context = TextContext::default();
+                text.into_iter()
+                    .map(|lts| disambiguate(lts, &context))
+                    .collect()
+            })
+        } else {
+            let document = (uses_document && complete).then(|| DocumentBag::new(&window));
+            let ranges: Vec<Range<usize>> = (num_written..ready)
+                .step_by(batch_size)
+                .map(|start| start..(start + batch_size).min(ready))
+                .collect();
+            let window = &window;
+            run_batches(pool.as_ref(), ranges, |range| {
+                range
+                    .map(|i| {
+                        let context = TextContext {
+                            before: &window[i.saturating_sub(n)..i],
+                            after: &window
+                                [i + 1..i.saturating_add(n).saturating_add(1).min(window.len())],
+                            document: document.as_ref(),
+                            targets: None,
+                        };
+                        disambiguate(window[i].clone(), &context)
+                    })
+                    .collect()
+            })
+        };
+        for sentence in disambiguated.into_iter().flatten() {
+            sink(f, sentence)?;
         }
-
-        if total_sentences >= max_sen {
-            break;
+        if complete {
+            window.clear();
+            num_written = 0;
+        } else {
+            let done = ready.saturating_sub(n);
+            window.drain(..done.min(window.len()));
+            num_written = ready - done;
         }
     }
-    // }
-
-    // if !args.for_lemma.is_none() {
-    //     todo!("printRatios(ratios)");
-    // }
     Ok(())
 }
-fn join_to_string(vs: &[f32]) -> String {
-    if vs.is_empty() {
-        return String::new();
-    }
-    let mut out = String::with_capacity(vs.len() * 2);
-    out.push_str(&vs[0].to_string());
-    for v in &vs[1..] {
-        out.push_str(&format!("|{}", v));
+
+/// A sentence without scores.
+fn unscored(lts: Vec<LemmaToken>) -> DisambiguatedSentence {
+    let disamb = vec![None; lts.len()];
+    (lts, disamb)
+}
+
+/// Run `job` on each batch, on the workers of `pool` if given, and collect
+/// the results in order.
+fn run_batches<T: Send, R: Send>(
+    pool: Option<&rayon::ThreadPool>,
+    batches: Vec<T>,
+    job: impl Fn(T) -> R + Send + Sync,
+) -> Vec<R> {
+    match pool {
+        Some(pool) => pool.install(|| batches.into_par_iter().map(job).collect()),
+        None => batches.into_iter().map(job).collect(),
     }
-    out
 }
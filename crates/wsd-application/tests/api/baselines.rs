@@ -0,0 +1,156 @@
+use std::{fs, io};
+
+use process_corpus::LemmaToken;
+use rstest::rstest;
+
+use wsd_application::{
+    FirstSenseWSD, JsonlFormat, MostFrequentSenseWSD, RandomWSD, SenseFrequencies, TabFormat,
+    WSDApplication, make_wsd_application, sense_ratios,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+const EXAMPLE: &str = "assets/testing/example1.jsonl";
+const SENSE_FREQS: &str = "assets/testing/sense_freqs.txt";
+
+fn token(senses: &str) -> LemmaToken {
+    LemmaToken::parse_line(&format!("1\tx\t_\t_\tx..nn\t{}", senses))
+}
+
+fn disambiguate(app_name: &str, argv: &[&str], opts: DisambiguateOptions) -> eyre::Result<String> {
+    let argv: Vec<String> = argv.iter().map(|a| a.to_string()).collect();
+    let wsd = make_wsd_application(None, app_name, &argv)?;
+    let input = fs::read_to_string(EXAMPLE)?;
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd,
+        &mut input.as_bytes(),
+        &mut out,
+        &mut JsonlFormat::new(),
+        opts,
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+#[rstest]
+#[case::in_order("x..1|x..2|x..3", Some(vec![1.0, 0.0, 0.0]))]
+#[case::out_of_order("x..3|x..1|x..2", Some(vec![0.0, 1.0, 0.0]))]
+#[case::unnumbered("x..a|x..2", Some(vec![0.0, 1.0]))]
+#[case::single("x..1", None)]
+fn test_first_sense(#[case] senses: &str, #[case] expected: Option<Vec<f32>>) {
+    assert_eq!(FirstSenseWSD.disambiguate(&[token(senses)], 0), expected);
+}
+
+#[test]
+fn test_read_sense_frequencies() -> eyre::Result<()> {
+    let freqs = SenseFrequencies::read(&mut io::BufReader::new(fs::File::open(SENSE_FREQS)?))?;
+
+    assert_eq!(freqs.get("utskott..2"), 12.0);
+    assert_eq!(freqs.get("anslag..3"), 0.0);
+    Ok(())
+}
+
+#[test]
+fn test_read_sense_ratio_tables() -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open("assets/testing/example1.in.txt")?);
+    let wsd = make_wsd_application(None, "FirstSenseWSD", &[])?;
+    let ratios = sense_ratios(
+        wsd,
+        &mut reader,
+        &mut TabFormat::default(),
+        &["anslag", "utskott"],
+        10,
+        usize::MAX,
+    )?;
+    let table = ratios.to_string();
+
+    let freqs = SenseFrequencies::read(&mut table.as_bytes())?;
+
+    for (target, ratios) in &ratios.lemmas {
+        assert!(ratios.scored > 0, "{}", target);
+        for sense in ratios.expected.keys() {
+            let expected = (ratios.expected_ratio(sense) * 1000.0).round() / 1000.0;
+            assert_eq!(freqs.get(sense), expected, "{}", sense);
+        }
+    }
+    Ok(())
+}
+
+#[rstest]
+#[case::no_count("anslag..1\n")]
+#[case::bad_count("anslag..1 often\n")]
+#[case::negative_count("anslag..1 -1\n")]
+#[case::extra_fields("anslag..1 1 2 3\n")]
+fn test_read_sense_frequencies_fails(#[case] input: &str) {
+    let err = SenseFrequencies::read(&mut input.as_bytes()).expect_err("bad line");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[rstest]
+#[case::proportional(0.0, "x..1|x..2|x..3", Some(vec![0.25, 0.75, 0.0]))]
+#[case::smoothed(1.0, "x..1|x..2|x..3", Some(vec![2.0 / 7.0, 4.0 / 7.0, 1.0 / 7.0]))]
+#[case::unseen(1.0, "x..4|x..3", Some(vec![0.0, 1.0]))]
+#[case::single(0.0, "x..2", None)]
+fn test_most_frequent_sense(
+    #[case] smoothing: f64,
+    #[case] senses: &str,
+    #[case] expected: Option<Vec<f32>>,
+) {
+    let freqs = SenseFrequencies::from_counts([("x..1", 1.0), ("x..2", 3.0)]);
+    let wsd = MostFrequentSenseWSD::new(freqs, smoothing);
+
+    assert_eq!(wsd.disambiguate(&[token(senses)], 0), expected);
+}
+
+#[test]
+fn test_random_is_seeded() {
+    let lts: Vec<LemmaToken> = (0..64).map(|_| token("x..1|x..2|x..3|x..4")).collect();
+    let picks = |seed: u64| -> Vec<Option<Vec<f32>>> {
+        (0..lts.len())
+            .map(|i| RandomWSD::new(seed).disambiguate(&lts, i))
+            .collect()
+    };
+
+    assert_eq!(picks(1), picks(1));
+    assert_ne!(picks(1), picks(2));
+    for probs in picks(1) {
+        let probs = probs.expect("4 senses");
+        assert_eq!(probs.iter().filter(|&&p| p == 1.0).count(), 1);
+        assert_eq!(probs.iter().sum::<f32>(), 1.0);
+    }
+    // every sense is picked for some token
+    for sense in 0..4 {
+        assert!(picks(1).iter().any(|p| p.as_ref().unwrap()[sense] == 1.0));
+    }
+    assert_eq!(RandomWSD::new(1).disambiguate(&[token("x..1")], 0), None);
+}
+
+#[rstest]
+#[case::first_sense("FirstSenseWSD", &[])]
+#[case::most_frequent_sense("MostFrequentSenseWSD", &["-senseFreqFile=assets/testing/sense_freqs.txt"])]
+#[case::random("RandomWSD", &["-seed=7"])]
+fn test_baseline_jsonl(#[case] app_name: &str, #[case] argv: &[&str]) -> eyre::Result<()> {
+    let out = disambiguate(app_name, argv, DisambiguateOptions::default())?;
+    let threaded = disambiguate(
+        app_name,
+        argv,
+        DisambiguateOptions {
+            batch_size: 2,
+            threads: 3,
+            ..DisambiguateOptions::default()
+        },
+    )?;
+
+    assert_eq!(threaded, out);
+    insta::assert_snapshot!(app_name, out);
+    Ok(())
+}
+
+#[rstest]
+#[case::no_freq_file("MostFrequentSenseWSD", &[])]
+#[case::missing_freq_file("MostFrequentSenseWSD", &["-senseFreqFile=assets/testing/no-such-file.txt"])]
+#[case::bad_smoothing("MostFrequentSenseWSD", &["-senseFreqFile=assets/testing/sense_freqs.txt", "-smoothing=-1"])]
+#[case::bad_seed("RandomWSD", &["-seed=lucky"])]
+fn test_baseline_bad_flags(#[case] app_name: &str, #[case] argv: &[&str]) {
+    let argv: Vec<String> = argv.iter().map(|a| a.to_string()).collect();
+    assert!(make_wsd_application(None, app_name, &argv).is_err());
+}
@@ -0,0 +1,47 @@
+use rstest::fixture;
+
+use wsd_application::{
+    ConlluFormat, JsonlFormat, SbXmlFormat, SharedWSDApplication, SourceFormat, TabFormat,
+    VrtFormat, make_wsd_application,
+};
+
+/// The flags of VectorWSD with the tiny test models.
+const TINY_MODELS: [&str; 5] = [
+    "-svFile=assets/testing/models/sense.bin",
+    "-cvFile=assets/testing/models/context.bin",
+    "-s1Prior=1",
+    "-decay=true",
+    "-contextWidth=10",
+];
+
+/// [`TINY_MODELS`] followed by `flags`, which override them.
+pub fn tiny_argv(flags: &[&str]) -> Vec<String> {
+    TINY_MODELS
+        .iter()
+        .chain(flags)
+        .map(|a| a.to_string())
+        .collect()
+}
+
+/// VectorWSD with the tiny test models.
+#[fixture]
+pub fn tiny_wsd() -> SharedWSDApplication {
+    tiny_wsd_with(&[])
+}
+
+/// VectorWSD with the tiny test models and `flags`.
+pub fn tiny_wsd_with(flags: &[&str]) -> SharedWSDApplication {
+    make_wsd_application(None, "VectorWSD", &tiny_argv(flags)).expect("VectorWSD created")
+}
+
+/// The source format called `name` in `-format=`.
+pub fn make_format(name: &str) -> Box<dyn SourceFormat> {
+    match name {
+        "tab" => Box::new(TabFormat::default()),
+        "sbxml" => Box::new(SbXmlFormat::new()),
+        "conllu" => Box::new(ConlluFormat::default()),
+        "vrt" => Box::new(VrtFormat::default()),
+        "jsonl" => Box::new(JsonlFormat::new()),
+        _ => unreachable!("unknown format {}", name),
+    }
+}
@@ -0,0 +1,98 @@
+use std::{fs, io};
+
+use process_corpus::LemmaToken;
+use rstest::rstest;
+
+use wsd_application::{
+    ConlluFormat, OutputFormat, SharedWSDApplication, SourceFormat,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+use crate::common::tiny_wsd;
+
+const EXAMPLE: &str = "assets/testing/example1.conllu";
+
+fn read_all(
+    format: &mut ConlluFormat,
+    reader: &mut dyn io::BufRead,
+    batch_size: usize,
+) -> eyre::Result<Vec<Vec<LemmaToken>>> {
+    let mut text = Vec::new();
+    loop {
+        let batch = format.read_sentences(reader, batch_size)?;
+        if batch.is_empty() {
+            return Ok(text);
+        }
+        text.extend(batch);
+    }
+}
+
+#[test]
+fn test_read_conllu() -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let text = read_all(&mut ConlluFormat::default(), &mut reader, 1)?;
+
+    let actual: Vec<Vec<String>> = text
+        .iter()
+        .map(|lts| lts.iter().map(LemmaToken::to_string).collect())
+        .collect();
+    insta::assert_debug_snapshot!(actual);
+    Ok(())
+}
+
+#[test]
+fn test_read_conllu_with_custom_sense_key() -> eyre::Result<()> {
+    let input =
+        "1\tanslag\tanslag\tNOUN\tNN\t_\t0\troot\t_\tSense=x..1|Saldo=anslag..1,anslag..2\n\n";
+    let mut reader = io::BufReader::new(input.as_bytes());
+    let text = ConlluFormat::new("Saldo").read_sentences(&mut reader, 10)?;
+
+    assert_eq!(text.len(), 1);
+    assert_eq!(text[0][0].possible_senses(), ["anslag..1", "anslag..2"]);
+    Ok(())
+}
+
+#[rstest]
+#[case::batch_1(1)]
+#[case::batch_100(100)]
+fn test_conllu_round_trip_without_scores(#[case] batch_size: usize) -> eyre::Result<()> {
+    let expected = fs::read_to_string(EXAMPLE)?;
+    let mut reader = io::BufReader::new(expected.as_bytes());
+    let mut format = ConlluFormat::default();
+    let mut out = Vec::new();
+    for lts in read_all(&mut format, &mut reader, batch_size)? {
+        let scores = vec![None; lts.len()];
+        format.write_sentence(&mut out, &(lts, scores))?;
+    }
+    format.finish(&mut out)?;
+
+    assert_eq!(String::from_utf8(out)?, expected);
+    Ok(())
+}
+
+#[rstest]
+fn test_disambiguate_conllu_tiny_models(
+    #[from(tiny_wsd)] wsd: SharedWSDApplication,
+) -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd,
+        &mut reader,
+        &mut out,
+        &mut ConlluFormat::default(),
+        DisambiguateOptions::default(),
+    )?;
+
+    insta::assert_snapshot!(String::from_utf8(out)?);
+    Ok(())
+}
+
+#[test]
+fn test_read_conllu_reports_missing_columns() {
+    let mut reader = io::BufReader::new("# sent_id = s1\n1\tanslag\tanslag\n\n".as_bytes());
+    let err = ConlluFormat::default()
+        .read_sentences(&mut reader, 10)
+        .expect_err("too few columns");
+    insta::assert_snapshot!(err.to_string());
+}
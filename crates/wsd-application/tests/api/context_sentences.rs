@@ -0,0 +1,279 @@
+use std::fs;
+
+use hashbrown::HashMap;
+use ndarray::{Array1, array};
+use process_corpus::LemmaToken;
+use rstest::rstest;
+
+use wsd_application::{
+    DocumentBag, SharedWSDApplication, TextContext, VectorWSD, VectorWSDOptions, WSDApplication,
+    make_wsd_application,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+use crate::common::{make_format, tiny_argv, tiny_wsd, tiny_wsd_with};
+
+fn disambiguate(
+    wsd: &SharedWSDApplication,
+    name: &str,
+    input: &str,
+    opts: DisambiguateOptions,
+) -> eyre::Result<String> {
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd.clone(),
+        &mut input.as_bytes(),
+        &mut out,
+        &mut make_format(name),
+        opts,
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// `bank` alone in a sentence, and `pengar` in the next, which only
+/// favours `bank..2` when seen as context.
+fn bank_wsd(options: VectorWSDOptions) -> VectorWSD {
+    let sense_vectors: HashMap<String, Array1<f32>> = HashMap::from([
+        ("bank..1".to_string(), array![1.0, 0.0]),
+        ("bank..2".to_string(), array![0.0, 1.0]),
+    ]);
+    let ctx_vectors: HashMap<String, Array1<f32>> =
+        HashMap::from([("pengar..nn".to_string(), array![0.0, 4.0])]);
+    VectorWSD::new(
+        VectorWSDOptions {
+            s1prior: 0.0,
+            context_width: 2,
+            ..options
+        },
+        Box::new(sense_vectors),
+        Box::new(ctx_vectors),
+    )
+}
+
+fn bank_text() -> Vec<Vec<LemmaToken>> {
+    vec![
+        vec![LemmaToken::parse_line(
+            "1\tbank\t_\t_\tbank..nn\tbank..1|bank..2",
+        )],
+        vec![LemmaToken::parse_line("1\tpengar\t_\t_\tpengar..nn\t_")],
+    ]
+}
+
+#[test]
+fn test_context_from_next_sentence() {
+    let wsd = bank_wsd(VectorWSDOptions {
+        context_sentences: 1,
+        ..VectorWSDOptions::default()
+    });
+    let text = bank_text();
+
+    let (_, alone) = wsd.disambiguate_sentence(text[0].clone());
+    let (_, in_context) = wsd.disambiguate_in_context(
+        text[0].clone(),
+        &TextContext {
+            after: &text[1..],
+            ..TextContext::default()
+        },
+    );
+
+    assert_eq!(alone, [Some(vec![0.5, 0.5])]);
+    let probs = in_context[0].as_ref().expect("bank is ambiguous");
+    assert!(probs[1] > probs[0], "probs={:?}", probs);
+    assert_eq!(wsd.context_sentences(), 1);
+    assert!(!wsd.uses_document());
+}
+
+#[rstest]
+#[case::no_weight(0.0)]
+#[case::weight(1.0)]
+fn test_document_weight(#[case] document_weight: f32) {
+    let wsd = bank_wsd(VectorWSDOptions {
+        document_weight,
+        ..VectorWSDOptions::default()
+    });
+    let text = bank_text();
+    let document = DocumentBag::new(&text);
+
+    let (_, disamb) = wsd.disambiguate_in_context(
+        text[0].clone(),
+        &TextContext {
+            document: Some(&document),
+            ..TextContext::default()
+        },
+    );
+
+    let probs = disamb[0].as_ref().expect("bank is ambiguous");
+    assert_eq!(wsd.uses_document(), document_weight != 0.0);
+    if document_weight == 0.0 {
+        assert_eq!(probs, &[0.5, 0.5]);
+    } else {
+        assert!(probs[1] > probs[0], "probs={:?}", probs);
+    }
+}
+
+#[test]
+fn test_document_bag_counts_first_lemmas() {
+    let mut text = bank_text();
+    text[1].push(LemmaToken::parse_line(
+        "2\tpengar\t_\t_\tpengar..nn|peng..nn\t_",
+    ));
+    let document = DocumentBag::new(&text);
+
+    let lemmas: Vec<(&str, usize)> = document.lemmas().collect();
+
+    assert_eq!(lemmas, [("bank..nn", 1), ("pengar..nn", 2)]);
+    assert_ne!(DocumentBag::new(&text).id(), document.id());
+}
+
+#[rstest]
+#[case::jsonl(
+    "jsonl",
+    "[{\"word\":\"a\"}]\n\n\n[{\"word\":\"b\"}]\n[{\"word\":\"c\"}]\n"
+)]
+#[case::conllu(
+    "conllu",
+    "1\ta\t_\t_\t_\t_\t_\t_\t_\t_\n\n\n1\tb\t_\t_\t_\t_\t_\t_\t_\t_\n\n1\tc\t_\t_\t_\t_\t_\t_\t_\t_\n\n"
+)]
+#[case::conllu_newdoc(
+    "conllu",
+    "# newdoc\n1\ta\t_\t_\t_\t_\t_\t_\t_\t_\n\n# newdoc id = d2\n1\tb\t_\t_\t_\t_\t_\t_\t_\t_\n\n1\tc\t_\t_\t_\t_\t_\t_\t_\t_\n\n"
+)]
+#[case::vrt(
+    "vrt",
+    "<text>\n<sentence>\na\t_\t_\t_\t_\n</sentence>\n</text>\n<text id=\"2\">\n<sentence>\nb\t_\t_\t_\t_\n</sentence>\n<sentence>\nc\t_\t_\t_\t_\n</sentence>\n</text>\n"
+)]
+#[case::sbxml(
+    "sbxml",
+    "<corpus><text><sentence><token>a</token></sentence></text>\n<text><sentence><token>b</token></sentence><sentence><token>c</token></sentence></text></corpus>\n"
+)]
+fn test_read_documents(#[case] name: &str, #[case] input: &str) -> eyre::Result<()> {
+    let documents = make_format(name).read_documents(&mut input.as_bytes())?;
+
+    let words: Vec<Vec<Vec<&str>>> = documents
+        .iter()
+        .map(|d| {
+            d.iter()
+                .map(|s| s.iter().map(|lt| lt.word()).collect())
+                .collect()
+        })
+        .collect();
+    assert_eq!(words, [vec![vec!["a"]], vec![vec!["b"], vec!["c"]]]);
+    Ok(())
+}
+
+#[rstest]
+fn test_document_boundary_resets_context(
+    #[values(
+        &["-contextSentences=1"][..],
+        &["-documentWeight=0.5"][..],
+    )]
+    flags: &[&str],
+    #[values(1, 100)] batch_size: usize,
+) -> eyre::Result<()> {
+    let input = fs::read_to_string("assets/testing/example1.jsonl")?;
+    let (first, second) = input.split_once("\n\n").expect("two documents");
+    let wsd = tiny_wsd_with(flags);
+    let opts = DisambiguateOptions {
+        batch_size,
+        ..DisambiguateOptions::default()
+    };
+
+    let together = disambiguate(&wsd, "jsonl", &input, opts)?;
+    let apart = format!(
+        "{}\n{}",
+        disambiguate(&wsd, "jsonl", &format!("{}\n", first), opts)?,
+        disambiguate(&wsd, "jsonl", second, opts)?
+    );
+    let one_document = disambiguate(&wsd, "jsonl", &format!("{}\n{}", first, second), opts)?;
+
+    assert_eq!(together, apart);
+    assert_ne!(together.replace("\n\n", "\n"), one_document);
+    Ok(())
+}
+
+#[rstest]
+#[case::sbxml("sbxml", "assets/testing/example1.sbxml.xml")]
+#[case::jsonl("jsonl", "assets/testing/example1.jsonl")]
+fn test_context_sentences_zero_is_default(
+    #[case] name: &str,
+    #[case] path: &str,
+) -> eyre::Result<()> {
+    let input = fs::read_to_string(path)?;
+    let expected = disambiguate(&tiny_wsd(), name, &input, Default::default())?;
+    let actual = disambiguate(
+        &tiny_wsd_with(&["-contextSentences=0", "-documentWeight=0"]),
+        name,
+        &input,
+        Default::default(),
+    )?;
+
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[rstest]
+#[case::sbxml("sbxml", "assets/testing/example1.sbxml.xml")]
+#[case::jsonl("jsonl", "assets/testing/example1.jsonl")]
+fn test_context_gives_identical_output(
+    #[case] name: &str,
+    #[case] path: &str,
+    #[values(
+        &["-contextSentences=1"][..],
+        &["-contextSentences=18446744073709551615"][..],
+        &["-documentWeight=0.5"][..],
+        &["-contextSentences=1", "-documentWeight=0.5"][..]
+    )]
+    flags: &[&str],
+    #[values(1, 2, 100)] batch_size: usize,
+    #[values(1, 3)] threads: usize,
+) -> eyre::Result<()> {
+    // one document, so that the sentences are context for each other
+    let input = fs::read_to_string(path)?.replace("\n\n", "\n");
+    let wsd = tiny_wsd_with(flags);
+    let expected = disambiguate(&wsd, name, &input, DisambiguateOptions::default())?;
+    let actual = disambiguate(
+        &wsd,
+        name,
+        &input,
+        DisambiguateOptions {
+            batch_size,
+            threads,
+            ..DisambiguateOptions::default()
+        },
+    )?;
+
+    assert_eq!(actual, expected);
+    assert_ne!(
+        actual,
+        disambiguate(&tiny_wsd(), name, &input, Default::default())?
+    );
+    Ok(())
+}
+
+#[rstest]
+#[case::context_sentences("-contextSentences=1")]
+#[case::document_weight("-documentWeight=0.5")]
+fn test_context_respects_max_sen(#[case] flag: &str) -> eyre::Result<()> {
+    let input = fs::read_to_string("assets/testing/example1.jsonl")?;
+    let opts = DisambiguateOptions {
+        max_sen: 2,
+        ..DisambiguateOptions::default()
+    };
+    let out = disambiguate(&tiny_wsd_with(&[flag]), "jsonl", &input, opts)?;
+
+    // both sentences and the blank line between the documents
+    assert_eq!(input.lines().count(), 3);
+    assert_eq!(out.lines().count(), 3);
+    Ok(())
+}
+
+#[test]
+fn test_bad_context_flags() {
+    for flag in ["-contextSentences=-1", "-documentWeight=much"] {
+        assert!(
+            make_wsd_application(None, "VectorWSD", &tiny_argv(&[flag])).is_err(),
+            "{}",
+            flag
+        );
+    }
+}
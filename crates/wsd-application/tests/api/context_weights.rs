@@ -0,0 +1,204 @@
+use std::{fs, io};
+
+use hashbrown::HashMap;
+use ndarray::{Array1, array};
+use process_corpus::LemmaToken;
+use rstest::rstest;
+
+use wsd_application::{
+    JsonlFormat, Kernel, LemmaIdf, VectorWSD, VectorWSDOptions, WSDApplication,
+    make_wsd_application,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+use crate::common::tiny_argv;
+
+#[rstest]
+#[case::uniform("uniform", Kernel::Uniform)]
+#[case::linear("linear", Kernel::Linear)]
+#[case::exponential("exponential", Kernel::Exponential { rate: Kernel::DEFAULT_RATE })]
+#[case::exponential_rate("exponential:0.25", Kernel::Exponential { rate: 0.25 })]
+#[case::gaussian("gaussian:2", Kernel::Gaussian { sigma: 2.0 })]
+#[case::inverse("inverse", Kernel::InverseDistance)]
+fn test_parse_kernel(#[case] s: &str, #[case] expected: Kernel) {
+    assert_eq!(s.parse::<Kernel>().ok(), Some(expected));
+}
+
+#[rstest]
+#[case::unknown("triangle")]
+#[case::param_not_taken("linear:2")]
+#[case::bad_param("gaussian:wide")]
+#[case::negative_param("exponential:-1")]
+fn test_parse_kernel_fails(#[case] s: &str) {
+    assert!(s.parse::<Kernel>().is_err());
+}
+
+#[rstest]
+fn test_kernel_weights(
+    #[values(
+        Kernel::Uniform,
+        Kernel::Linear,
+        Kernel::Exponential { rate: 0.5 },
+        Kernel::Gaussian { sigma: 2.0 },
+        Kernel::InverseDistance
+    )]
+    kernel: Kernel,
+    #[values((3, 3), (1, 4), (0, 2))] (left, right): (usize, usize),
+) {
+    let (lw, rw) = kernel.weights(left, right);
+
+    assert_eq!((lw.len(), rw.len()), (left, right));
+    let total: f32 = lw.iter().chain(&rw).sum();
+    assert!((total - 1.0).abs() < 1e-6, "total={}", total);
+    for side in [&lw, &rw] {
+        assert!(side.iter().all(|&w| w > 0.0), "{:?}", side);
+        assert!(side.windows(2).all(|w| w[0] >= w[1]), "{:?}", side);
+    }
+}
+
+#[test]
+fn test_linear_kernel_falls_to_zero_past_the_edge() {
+    let (left, right) = Kernel::Linear.weights(3, 3);
+
+    assert_eq!(left, right);
+    for (w, expected) in left.iter().zip([3.0 / 12.0, 2.0 / 12.0, 1.0 / 12.0]) {
+        assert!((w - expected).abs() < 1e-6, "{:?}", left);
+    }
+}
+
+#[test]
+fn test_read_lemma_idf() -> eyre::Result<()> {
+    let input = "# lemma count\nav..pp 90\n\nutskott..nn 4\nutskott..nn 5\n";
+    let idf = LemmaIdf::read(&mut input.as_bytes())?;
+
+    assert_eq!(
+        idf,
+        LemmaIdf::from_counts([("av..pp", 90), ("utskott..nn", 9)])
+    );
+    assert!((idf.get("av..pp") - (100f32 / 91.0).ln()).abs() < 1e-6);
+    assert!((idf.get("utskott..nn") - (100f32 / 10.0).ln()).abs() < 1e-6);
+    assert!((idf.get("anslag..nn") - 100f32.ln()).abs() < 1e-6);
+    Ok(())
+}
+
+#[rstest]
+#[case::no_count("av..pp\n")]
+#[case::bad_count("av..pp many\n")]
+#[case::extra_field("av..pp 1 2\n")]
+fn test_read_lemma_idf_fails(#[case] input: &str) {
+    let err = LemmaIdf::read(&mut input.as_bytes()).expect_err("bad line");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+/// `bank` between `flod`, favouring `bank..1`, and `pengar`, favouring `bank..2`.
+fn bank_wsd(options: VectorWSDOptions) -> VectorWSD {
+    let sense_vectors: HashMap<String, Array1<f32>> = HashMap::from([
+        ("bank..1".to_string(), array![1.0, 0.0]),
+        ("bank..2".to_string(), array![0.0, 1.0]),
+    ]);
+    let ctx_vectors: HashMap<String, Array1<f32>> = HashMap::from([
+        ("flod..nn".to_string(), array![3.0, 0.0]),
+        ("pengar..nn".to_string(), array![0.0, 4.0]),
+    ]);
+    VectorWSD::new(
+        VectorWSDOptions {
+            context_width: 2,
+            ..options
+        },
+        Box::new(sense_vectors),
+        Box::new(ctx_vectors),
+    )
+}
+
+fn bank_sentence() -> Vec<LemmaToken> {
+    [
+        "1\tflod\t_\t_\tflod..nn\t_",
+        "2\tbank\t_\t_\tbank..nn\tbank..1|bank..2",
+        "3\tpengar\t_\t_\tpengar..nn\t_",
+    ]
+    .map(LemmaToken::parse_line)
+    .to_vec()
+}
+
+/// The probability of `bank..2`, checking that both scoring paths agree.
+fn p_bank_2(wsd: &VectorWSD) -> f32 {
+    let lts = bank_sentence();
+    let probs = wsd.disambiguate(&lts, 1).expect("bank is ambiguous");
+    let (_, disamb) = wsd.disambiguate_sentence(lts);
+    let sentence_probs = disamb[1].as_ref().expect("bank is ambiguous");
+    for (a, b) in probs.iter().zip(sentence_probs) {
+        assert!((a - b).abs() < 1e-6, "{:?} != {:?}", probs, sentence_probs);
+    }
+    probs[1]
+}
+
+#[rstest]
+#[case::both_sides(None, None, true)]
+#[case::left_only(None, Some(0), false)]
+#[case::right_only(Some(0), None, true)]
+fn test_asymmetric_width(
+    #[case] left_width: Option<usize>,
+    #[case] right_width: Option<usize>,
+    #[case] favours_bank_2: bool,
+) {
+    let wsd = bank_wsd(VectorWSDOptions {
+        kernel: Some(Kernel::Uniform),
+        left_width,
+        right_width,
+        ..VectorWSDOptions::default()
+    });
+
+    assert_eq!(p_bank_2(&wsd) > 0.5, favours_bank_2);
+}
+
+#[test]
+fn test_idf_weighs_down_frequent_lemmas() {
+    let options = VectorWSDOptions {
+        kernel: Some(Kernel::Exponential { rate: 0.5 }),
+        ..VectorWSDOptions::default()
+    };
+    let idf = LemmaIdf::from_counts([("pengar..nn", 1000), ("flod..nn", 1)]);
+
+    assert!(p_bank_2(&bank_wsd(options)) > 0.5);
+    assert!(p_bank_2(&bank_wsd(options).with_idf(idf)) < 0.5);
+}
+
+fn disambiguate_example(flags: &[&str]) -> eyre::Result<String> {
+    let argv: Vec<&str> = ["-decay=false"].iter().chain(flags).copied().collect();
+    let wsd = make_wsd_application(None, "VectorWSD", &tiny_argv(&argv))?;
+    let input = fs::read_to_string("assets/testing/example1.jsonl")?;
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd,
+        &mut input.as_bytes(),
+        &mut out,
+        &mut JsonlFormat::new(),
+        DisambiguateOptions::default(),
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+#[test]
+fn test_uniform_kernel_is_no_decay() -> eyre::Result<()> {
+    assert_eq!(
+        disambiguate_example(&["-kernel=uniform"])?,
+        disambiguate_example(&["-decay=false"])?
+    );
+    assert_eq!(
+        disambiguate_example(&["-decay=true", "-leftWidth=10", "-rightWidth=10"])?,
+        disambiguate_example(&["-decay=true"])?
+    );
+    assert_ne!(
+        disambiguate_example(&["-kernel=gaussian:2"])?,
+        disambiguate_example(&["-decay=false"])?
+    );
+    Ok(())
+}
+
+#[rstest]
+#[case::kernel("-kernel=cosine")]
+#[case::left_width("-leftWidth=-1")]
+#[case::idf_file("-idfFile=assets/testing/no-such-file.txt")]
+fn test_bad_weighting_flags(#[case] flag: &str) {
+    assert!(disambiguate_example(&[flag]).is_err());
+}
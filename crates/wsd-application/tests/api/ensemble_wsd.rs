@@ -0,0 +1,284 @@
+use std::{fs, io, sync::Arc};
+
+use process_corpus::LemmaToken;
+use rstest::rstest;
+
+use wsd_application::{
+    Combination, EnsembleMember, EnsembleWSD, GoldKey, SharedWSDApplication, SourceFormat,
+    TabFormat, TextContext, UsageError, WSDApplication, WSDError, evaluate, make_wsd_application,
+    wsd_application::{DisambiguateOptions, disambiguate_document},
+};
+
+use crate::common::{tiny_argv, tiny_wsd_with};
+
+const EXAMPLE: &str = "assets/testing/example1.in.txt";
+const SENSE_FREQS: &str = "-MostFrequentSenseWSD.senseFreqFile=assets/testing/sense_freqs.txt";
+
+const SINGLE_FREQS: &str = "-senseFreqFile=assets/testing/sense_freqs.txt";
+
+const KEY: &str = "\
+1.17 utskott..2
+1.21 anvisa..1
+1.22 anslag..1
+1.59 anslag..3
+";
+
+/// Gives the same distribution to every ambiguous token.
+struct Fixed(Option<Vec<f32>>);
+
+impl WSDApplication for Fixed {
+    fn disambiguate(&self, _lts: &[LemmaToken], _i: usize) -> Option<Vec<f32>> {
+        self.0.clone()
+    }
+}
+
+/// Asks for `n` sentences of context and puts the number it got as the
+/// probability of the first sense.
+struct CountsContext(usize);
+
+impl WSDApplication for CountsContext {
+    fn disambiguate(&self, _lts: &[LemmaToken], _i: usize) -> Option<Vec<f32>> {
+        None
+    }
+
+    fn context_sentences(&self) -> usize {
+        self.0
+    }
+
+    fn disambiguate_in_context(
+        &self,
+        lts: Vec<LemmaToken>,
+        context: &TextContext<'_>,
+    ) -> (Vec<LemmaToken>, Vec<Option<Vec<f32>>>) {
+        let n = (context.before.len() + context.after.len()) as f32;
+        let disamb = vec![Some(vec![n, 0.0]); lts.len()];
+        (lts, disamb)
+    }
+}
+
+fn member(name: &str, weight: f32, wsd: impl WSDApplication + 'static) -> EnsembleMember {
+    EnsembleMember {
+        name: name.to_string(),
+        alias: name.to_string(),
+        weight,
+        wsd: Arc::new(wsd) as SharedWSDApplication,
+    }
+}
+
+fn token(senses: &str) -> LemmaToken {
+    LemmaToken::parse_line(&format!("1\tx\t_\t_\tx..nn\t{}", senses))
+}
+
+fn argv(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+fn assert_close(actual: Option<Vec<f32>>, expected: &[f32]) {
+    let actual = actual.expect("a distribution");
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+    }
+}
+
+#[rstest]
+#[case::average(Combination::Average, &[0.5, 0.35, 0.15])]
+#[case::product(Combination::Product, &[0.6093, 0.3780, 0.0126])]
+#[case::vote(Combination::Vote, &[0.75, 0.25, 0.0])]
+fn test_combine(#[case] combination: Combination, #[case] expected: &[f32]) {
+    let wsd = EnsembleWSD::new(
+        vec![
+            member("a", 3.0, Fixed(Some(vec![0.6, 0.2, 0.2]))),
+            member("b", 1.0, Fixed(Some(vec![0.2, 0.8, 0.0]))),
+        ],
+        combination,
+    );
+
+    assert_close(wsd.disambiguate(&[token("x..1|x..2|x..3")], 0), expected);
+}
+
+#[rstest]
+fn test_members_without_answer_are_left_out(
+    #[values(Combination::Average, Combination::Product, Combination::Vote)]
+    combination: Combination,
+) {
+    let wsd = EnsembleWSD::new(
+        vec![
+            member("none", 1.0, Fixed(None)),
+            member("short", 1.0, Fixed(Some(vec![1.0]))),
+            member("unweighted", 0.0, Fixed(Some(vec![1.0, 0.0]))),
+            member("b", 1.0, Fixed(Some(vec![0.0, 1.0]))),
+        ],
+        combination,
+    );
+    let lts = vec![token("x..1|x..2")];
+
+    assert_close(wsd.disambiguate(&lts, 0), &[0.0, 1.0]);
+    let (_, disamb) = wsd.disambiguate_sentence(lts);
+    assert_close(disamb[0].clone(), &[0.0, 1.0]);
+
+    let nobody = EnsembleWSD::new(vec![member("none", 1.0, Fixed(None))], combination);
+    assert_eq!(nobody.disambiguate(&[token("x..1|x..2")], 0), None);
+}
+
+#[test]
+fn test_members_get_the_context_they_ask_for() {
+    let wsd = EnsembleWSD::new(
+        vec![member("one", 1.0, CountsContext(1))],
+        Combination::Average,
+    );
+    let wider = EnsembleWSD::new(
+        vec![
+            member("one", 1.0, CountsContext(1)),
+            member("three", 0.0, CountsContext(3)),
+        ],
+        Combination::Average,
+    );
+    let sentences: Vec<Vec<LemmaToken>> = (0..3).map(|_| vec![token("x..1|x..2")]).collect();
+    let context = TextContext {
+        before: &sentences[..2],
+        after: &sentences[2..],
+        document: None,
+        targets: None,
+    };
+
+    assert_eq!(wsd.context_sentences(), 1);
+    assert_eq!(wider.context_sentences(), 3);
+    let (_, disamb) = wider.disambiguate_in_context(vec![token("x..1|x..2")], &context);
+    // the first member got one sentence on each side
+    assert_eq!(disamb, vec![Some(vec![2.0, 0.0])]);
+}
+
+#[test]
+fn test_members_keep_their_own_document_summaries() -> eyre::Result<()> {
+    let vector = |weight| EnsembleMember {
+        name: "VectorWSD".to_string(),
+        alias: "VectorWSD".to_string(),
+        weight,
+        wsd: tiny_wsd_with(&["-documentWeight=0.5"]),
+    };
+    let wsd = EnsembleWSD::new(vec![vector(0.5), vector(0.5)], Combination::Average);
+    let text = TabFormat::default().read_sentences(
+        &mut io::BufReader::new(fs::File::open(EXAMPLE)?),
+        usize::MAX,
+    )?;
+
+    let expected = disambiguate_document(vector(1.0).wsd.as_ref(), text.clone());
+    let actual = disambiguate_document(&wsd, text);
+
+    assert!(wsd.uses_document());
+    assert_eq!(actual.len(), expected.len());
+    for ((_, actual), (_, expected)) in actual.into_iter().zip(expected) {
+        for (a, e) in actual.into_iter().zip(expected) {
+            match e {
+                Some(e) => assert_close(a, &e),
+                None => assert_eq!(a, None),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_from_args_passes_prefixed_flags() -> eyre::Result<()> {
+    let wsd = EnsembleWSD::from_args(
+        None,
+        &argv(&[
+            "-members=FirstSenseWSD:0.25,MostFrequentSenseWSD:0.75",
+            "-combine=vote",
+            SENSE_FREQS,
+        ]),
+    )?;
+
+    assert_eq!(
+        wsd.members_spec(),
+        "FirstSenseWSD:0.25,MostFrequentSenseWSD:0.75"
+    );
+    // utskott..2 is the most frequent sense
+    assert_close(
+        wsd.disambiguate(&[token("utskott..1|utskott..2|utskott..3")], 0),
+        &[0.25, 0.75, 0.0],
+    );
+    Ok(())
+}
+
+#[test]
+fn test_members_with_aliases_get_their_own_flags() -> eyre::Result<()> {
+    let prefixed = |alias: &str, flags: &[&str]| -> Vec<String> {
+        tiny_argv(flags)
+            .iter()
+            .map(|a| format!("-{}.{}", alias, &a[1..]))
+            .collect()
+    };
+    let mut args = argv(&["-members=VectorWSD:narrow,VectorWSD:wide:0.5"]);
+    args.extend(prefixed("narrow", &["-contextWidth=2"]));
+    args.extend(prefixed("wide", &["-contextSentences=2"]));
+
+    let wsd = EnsembleWSD::from_args(None, &args)?;
+
+    assert_eq!(wsd.members_spec(), "VectorWSD:narrow:1,VectorWSD:wide:0.5");
+    assert_eq!(wsd.members()[0].wsd.context_sentences(), 0);
+    assert_eq!(wsd.members()[1].wsd.context_sentences(), 2);
+    assert_eq!(wsd.context_sentences(), 2);
+    Ok(())
+}
+
+#[rstest]
+#[case::no_members(&[])]
+#[case::unknown_member(&["-members=FirstSenseWSD,NoSuchWSD"])]
+#[case::nested(&["-members=EnsembleWSD"])]
+#[case::bad_weight(&["-members=FirstSenseWSD:first:heavy"])]
+#[case::too_many_fields(&["-members=FirstSenseWSD:first:1:2"])]
+#[case::same_alias(&["-members=FirstSenseWSD,RandomWSD:FirstSenseWSD"])]
+#[case::unprefixed_flags(&["-members=MostFrequentSenseWSD", SINGLE_FREQS])]
+#[case::negative_weight(&["-members=FirstSenseWSD:-1"])]
+#[case::bad_combination(&["-members=FirstSenseWSD", "-combine=max"])]
+#[case::member_flags(&["-members=MostFrequentSenseWSD"])]
+fn test_ensemble_bad_flags(#[case] args: &[&str]) {
+    let result = make_wsd_application(None, "EnsembleWSD", &argv(args));
+    assert!(matches!(result, Err(WSDError::UsageError { .. })));
+}
+
+#[test]
+fn test_unknown_member_is_bad_value() {
+    let err = EnsembleWSD::from_args(None, &argv(&["-members=NoSuchWSD"])).err();
+    assert!(
+        matches!(err, Some(UsageError::BadValue { .. })),
+        "{:?}",
+        err
+    );
+}
+
+#[rstest]
+fn test_tune(#[values("average", "product", "vote")] combination: &str) -> eyre::Result<()> {
+    let key = GoldKey::read(&mut KEY.as_bytes())?;
+    let documents =
+        TabFormat::default().read_documents(&mut io::BufReader::new(fs::File::open(EXAMPLE)?))?;
+    let args = argv(&[
+        "-members=FirstSenseWSD,MostFrequentSenseWSD,RandomWSD",
+        &format!("-combine={}", combination),
+        SENSE_FREQS,
+    ]);
+    let correct = |wsd: SharedWSDApplication| -> eyre::Result<_> {
+        let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+        let opts = DisambiguateOptions {
+            batch_size: 10,
+            ..DisambiguateOptions::default()
+        };
+        Ok(evaluate(wsd, &mut reader, &mut TabFormat::default(), &key, opts)?.total)
+    };
+    let mut wsd = EnsembleWSD::from_args(None, &args)?;
+
+    let scores = wsd.tune(&documents, &key, 0.25);
+
+    assert_eq!(scores.instances, key.len());
+    for name in ["FirstSenseWSD", "MostFrequentSenseWSD", "RandomWSD"] {
+        let single = correct(make_wsd_application(None, name, &argv(&[SINGLE_FREQS]))?)?;
+        assert!(scores.correct >= single.correct, "{}", name);
+    }
+    let weights: f32 = wsd.members().iter().map(|m| m.weight).sum();
+    assert!((weights - 1.0).abs() < 1e-6);
+    insta::assert_snapshot!(combination, wsd.members_spec());
+    assert_eq!(correct(Arc::new(wsd))?, scores);
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use std::{fs, io};
+
+use rstest::rstest;
+
+use wsd_application::{
+    EvalScores, GoldKey, SharedWSDApplication, TabFormat, evaluate,
+    wsd_application::DisambiguateOptions,
+};
+
+use crate::common::tiny_wsd;
+
+const EXAMPLE: &str = "assets/testing/example1.in.txt";
+
+const KEY: &str = "\
+# instance gold senses
+1.1 arbetsmarknadsutskott..1
+1.17 utskott..2
+1.21 anvisa..1
+1.22 anslag..1 anslag..2
+1.23 en..2
+1.59 anslag..3
+2.1 saknas..1
+";
+
+#[rstest]
+fn test_evaluate_tiny_models(#[from(tiny_wsd)] wsd: SharedWSDApplication) -> eyre::Result<()> {
+    let key = GoldKey::read(&mut KEY.as_bytes())?;
+    let report_with = |threads| -> eyre::Result<_> {
+        let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+        let opts = DisambiguateOptions {
+            batch_size: 10,
+            threads,
+            ..DisambiguateOptions::default()
+        };
+        Ok(evaluate(
+            wsd.clone(),
+            &mut reader,
+            &mut TabFormat::default(),
+            &key,
+            opts,
+        )?)
+    };
+    let report = report_with(1)?;
+
+    assert_eq!(report_with(3)?, report);
+    assert_eq!(report.total.instances, key.len());
+    // 2.1 is not in the input
+    assert_eq!(report.no_lemma.instances, 1);
+    assert!(!report.per_lemma.contains_key("saknas"));
+    insta::assert_snapshot!(report.to_string());
+    let mut json = Vec::new();
+    report.write_json(&mut json)?;
+    insta::assert_snapshot!(String::from_utf8(json)?);
+    Ok(())
+}
+
+#[test]
+fn test_eval_scores() {
+    let scores = EvalScores {
+        instances: 10,
+        attempted: 8,
+        correct: 6,
+    };
+    assert_eq!(scores.accuracy(), 0.6);
+    assert_eq!(scores.coverage(), 0.8);
+    assert_eq!(scores.precision(), 0.75);
+    assert_eq!(scores.recall(), 0.6);
+    assert!((scores.f1() - 2.0 / 3.0).abs() < 1e-12);
+    assert_eq!(EvalScores::default().f1(), 0.0);
+}
+
+#[rstest]
+#[case::bad_id("17 utskott..2\n")]
+#[case::no_sense("1.17\n")]
+#[case::duplicate("1.17 utskott..2\n1.17 utskott..1\n")]
+fn test_read_gold_key_error(#[case] input: &str) {
+    let err = GoldKey::read(&mut input.as_bytes()).expect_err("bad key");
+    insta::assert_snapshot!(err.to_string());
+}
@@ -0,0 +1,96 @@
+use std::{fs, io};
+
+use process_corpus::LemmaToken;
+use rstest::rstest;
+use serde_json::{Map, Value};
+
+use wsd_application::{
+    JsonlFormat, OutputFormat, SharedWSDApplication, SourceFormat,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+use crate::common::tiny_wsd;
+
+const EXAMPLE: &str = "assets/testing/example1.jsonl";
+
+#[test]
+fn test_read_jsonl() -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let text = JsonlFormat::new().read_sentences(&mut reader, 10)?;
+
+    let actual: Vec<Vec<String>> = text
+        .iter()
+        .map(|lts| lts.iter().map(LemmaToken::to_string).collect())
+        .collect();
+    insta::assert_debug_snapshot!(actual);
+    Ok(())
+}
+
+#[test]
+fn test_jsonl_passes_fields_through_without_scores() -> eyre::Result<()> {
+    let input = fs::read_to_string(EXAMPLE)?;
+    let mut reader = io::BufReader::new(input.as_bytes());
+    let mut format = JsonlFormat::new();
+    let mut out = Vec::new();
+    for lts in format.read_sentences(&mut reader, 10)? {
+        let scores = vec![None; lts.len()];
+        format.write_sentence(&mut out, &(lts, scores))?;
+    }
+
+    let parse =
+        |line: &str| -> eyre::Result<Vec<Map<String, Value>>> { Ok(serde_json::from_str(line)?) };
+    let expected: Vec<&str> = input.lines().collect();
+    let actual = String::from_utf8(out)?;
+    assert_eq!(actual.lines().count(), expected.len());
+    for (actual, expected) in actual.lines().zip(expected) {
+        if expected.is_empty() {
+            assert_eq!(actual, expected);
+            continue;
+        }
+        for (mut actual, mut expected) in parse(actual)?.into_iter().zip(parse(expected)?) {
+            // every token gets a map from its candidates to null
+            let candidates: Vec<String> = match expected.remove("senses") {
+                Some(Value::Array(senses)) => senses
+                    .iter()
+                    .filter_map(|s| s.as_str().map(String::from))
+                    .collect(),
+                Some(Value::Object(senses)) => senses.keys().cloned().collect(),
+                _ => Vec::new(),
+            };
+            let Some(Value::Object(senses)) = actual.remove("senses") else {
+                panic!("no senses map in {:?}", actual);
+            };
+            assert_eq!(senses.keys().cloned().collect::<Vec<_>>(), candidates);
+            assert!(senses.values().all(Value::is_null));
+            assert_eq!(actual, expected);
+        }
+    }
+    Ok(())
+}
+
+#[rstest]
+fn test_disambiguate_jsonl_tiny_models(
+    #[from(tiny_wsd)] wsd: SharedWSDApplication,
+) -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd,
+        &mut reader,
+        &mut out,
+        &mut JsonlFormat::new(),
+        DisambiguateOptions::default(),
+    )?;
+
+    insta::assert_snapshot!(String::from_utf8(out)?);
+    Ok(())
+}
+
+#[test]
+fn test_read_jsonl_reports_token_without_word() {
+    let mut reader = io::BufReader::new("[{\"word\":\"a\"}]\n[{\"lemmas\":[]}]\n".as_bytes());
+    let err = JsonlFormat::new()
+        .read_sentences(&mut reader, 10)
+        .expect_err("missing word");
+    insta::assert_snapshot!(err.to_string());
+}
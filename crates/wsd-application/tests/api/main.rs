@@ -1 +1,20 @@
+mod baselines;
+mod common;
+mod conllu_format;
+mod context_sentences;
+mod context_weights;
+mod ensemble_wsd;
+mod evaluation;
+mod jsonl_format;
+mod model_bundle;
+mod model_check;
+mod output_format;
+mod page_rank_wsd;
+mod sbxml_format;
+mod sense_backoff;
+mod sense_ratios;
+mod sentences;
+mod tab_format;
+mod threads;
 mod vector_wsd;
+mod vrt_format;
@@ -0,0 +1,157 @@
+use std::{fs, io, path::PathBuf};
+
+use wsd_application::{
+    BundleHyperparameters, Kernel, ModelBundle, UsageError, VectorWSD, VectorWSDOptions, WSDError,
+    make_wsd_application,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+use crate::common::tiny_argv;
+
+fn create_test_bundle(name: &str) -> eyre::Result<PathBuf> {
+    create_test_bundle_with(name, tiny_options(), None)
+}
+
+/// The options of [`tiny_argv`].
+fn tiny_options() -> VectorWSDOptions {
+    VectorWSDOptions {
+        decay: true,
+        s1prior: 1.0,
+        context_width: 10,
+        ..VectorWSDOptions::default()
+    }
+}
+
+fn create_test_bundle_with(
+    name: &str,
+    options: VectorWSDOptions,
+    idf_file: Option<&str>,
+) -> eyre::Result<PathBuf> {
+    let hyperparameters: BundleHyperparameters = options.into();
+    let dir = std::env::temp_dir().join(format!("wsd-bundle-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    ModelBundle::create(
+        &dir,
+        "assets/testing/models/sense.bin",
+        "assets/testing/models/context.bin",
+        idf_file,
+        hyperparameters,
+        Some("test".into()),
+        Some("assets/testing/example1.in.txt".into()),
+    )?;
+    Ok(dir)
+}
+
+fn disambiguate_example(argv: &[String]) -> eyre::Result<String> {
+    let wsd = make_wsd_application(None, "VectorWSD", argv)?;
+    let mut reader = io::BufReader::new(fs::File::open("assets/testing/example1.in.txt")?);
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd,
+        &mut reader,
+        &mut out,
+        &mut wsd_application::TabFormat::default(),
+        DisambiguateOptions::default(),
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+#[test]
+fn test_bundle_matches_explicit_flags() -> eyre::Result<()> {
+    let dir = create_test_bundle("explicit")?;
+
+    let from_bundle = disambiguate_example(&[format!("-model={}", dir.display())])?;
+    let from_flags = disambiguate_example(&tiny_argv(&[]))?;
+
+    assert_eq!(from_bundle, from_flags);
+    let bundle = ModelBundle::open(&dir)?;
+    assert_eq!(bundle.manifest().saldo_version.as_deref(), Some("test"));
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_flags_override_bundle() -> eyre::Result<()> {
+    let dir = create_test_bundle("override")?;
+
+    let overridden = disambiguate_example(&[
+        format!("-model={}", dir.display()),
+        "-contextWidth=2".into(),
+    ])?;
+    let expected = disambiguate_example(&tiny_argv(&["-contextWidth=2"]))?;
+
+    assert_eq!(overridden, expected);
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_bundle_checksum_mismatch() -> eyre::Result<()> {
+    let dir = create_test_bundle("checksum")?;
+    fs::write(dir.join("context.bin"), b"1 1\n")?;
+
+    let result = VectorWSD::from_args(None, &[format!("-model={}", dir.display())]);
+
+    assert!(matches!(result, Err(UsageError::ChecksumMismatch { .. })));
+    let result = make_wsd_application(None, "VectorWSD", &[format!("-model={}", dir.display())]);
+    assert!(matches!(result, Err(WSDError::UsageError { .. })));
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_bundle_saldo_version() -> eyre::Result<()> {
+    let dir = create_test_bundle("saldo-version")?;
+    let argv = |version: &str| {
+        [
+            format!("-model={}", dir.display()),
+            format!("-saldoVersion={}", version),
+        ]
+    };
+
+    assert!(VectorWSD::from_args(None, &argv("test")).is_ok());
+    let result = VectorWSD::from_args(None, &argv("2.3"));
+    assert!(matches!(
+        result,
+        Err(UsageError::SaldoVersionMismatch { .. })
+    ));
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_bundle_keeps_all_options() -> eyre::Result<()> {
+    let idf_path = std::env::temp_dir().join(format!("wsd-idf-{}.txt", std::process::id()));
+    fs::write(&idf_path, "att..sn 90\nutskott..nn 9\n")?;
+    let idf_file = idf_path.to_str().expect("UTF-8 temp dir");
+    let flags = [
+        "-kernel=gaussian:2",
+        "-leftWidth=3",
+        "-rightWidth=5",
+        "-contextSentences=1",
+        "-documentWeight=0.5",
+    ];
+    let mut options = tiny_options();
+    options.update_from_args(&flags.map(String::from))?;
+    let dir = create_test_bundle_with("all-options", options, Some(idf_file))?;
+
+    let from_bundle = disambiguate_example(&[format!("-model={}", dir.display())])?;
+    let mut argv = flags.to_vec();
+    let idf_flag = format!("-idfFile={}", idf_file);
+    argv.push(&idf_flag);
+    let from_flags = disambiguate_example(&tiny_argv(&argv))?;
+
+    assert_eq!(from_bundle, from_flags);
+    let hyperparameters = ModelBundle::open(&dir)?.manifest().hyperparameters;
+    assert_eq!(
+        hyperparameters.kernel,
+        Some(Kernel::Gaussian { sigma: 2.0 })
+    );
+    assert_eq!(hyperparameters, options.into());
+    fs::write(dir.join("idf.txt"), "changed 1\n")?;
+    let result = VectorWSD::from_args(None, &[format!("-model={}", dir.display())]);
+    assert!(matches!(result, Err(UsageError::ChecksumMismatch { .. })));
+    fs::remove_dir_all(&dir)?;
+    fs::remove_file(&idf_path)?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use hashbrown::HashMap;
+use ndarray::{Array1, array};
+use saldo::SaldoLexicon;
+use wsd_application::{ModelReport, UsageError, VectorWSD, WSDError, make_wsd_application};
+
+#[test]
+fn test_model_report_with_saldo() -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new("assets/testing/saldo.xml")?;
+    let argv = &[
+        "-svFile=assets/testing/models/sense.bin".to_string(),
+        "-cvFile=assets/testing/models/context.bin".to_string(),
+    ];
+    let wsd = VectorWSD::from_args_unchecked(argv)?;
+
+    let report = wsd.check_models(Some(&saldo));
+
+    report.validate()?;
+    insta::assert_debug_snapshot!(report.saldo.map(|s| (
+        s.num_saldo_senses,
+        s.num_saldo_senses_with_vectors,
+        s.unknown_sense_ids.len(),
+        s.ambiguous_lemgrams_missing_vectors
+    )));
+    Ok(())
+}
+
+#[test]
+fn test_model_report_detects_dimension_mismatch() {
+    let sense_vectors: HashMap<String, Array1<f32>> =
+        HashMap::from([("bank..1".to_string(), array![1.0, 0.0])]);
+    let ctx_vectors: HashMap<String, Array1<f32>> =
+        HashMap::from([("pengar..nn".to_string(), array![0.0, 1.0, 0.0])]);
+
+    let report = ModelReport::new(&sense_vectors, &ctx_vectors, None);
+
+    assert!(matches!(
+        report.validate(),
+        Err(UsageError::DimensionMismatch {
+            sense_dim: 2,
+            context_dim: 3
+        })
+    ));
+}
+
+#[test]
+fn test_vector_wsd_fails_without_known_senses() -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new("assets/testing/saldo.xml")?;
+    let argv = &[
+        // the context model has lemma ids, not sense ids
+        "-svFile=assets/testing/models/context.bin".to_string(),
+        "-cvFile=assets/testing/models/context.bin".to_string(),
+    ];
+
+    let result = make_wsd_application(Some(&saldo), "VectorWSD", argv);
+
+    assert!(matches!(
+        result,
+        Err(WSDError::UsageError {
+            source: UsageError::NoKnownSenses { .. },
+            ..
+        })
+    ));
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::{fs, io};
+
+use rstest::rstest;
+
+use wsd_application::{
+    ConlluFormat, ConlluOutput, JsonOutput, JsonlFormat, OutputFormat, SbXmlFormat, SbXmlOutput,
+    SharedWSDApplication, SourceFormat, SparvOutput, TabFormat, TabOutput, VrtFormat, VrtOutput,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences_with_output},
+};
+
+use crate::common::tiny_wsd;
+
+fn disambiguate(
+    wsd: SharedWSDApplication,
+    input: &str,
+    f: &mut dyn SourceFormat,
+    output: Option<&mut dyn OutputFormat>,
+) -> eyre::Result<String> {
+    let mut reader = io::BufReader::new(fs::File::open(input)?);
+    let mut out = Vec::new();
+    disambiguate_sentences_with_output(
+        wsd,
+        &mut reader,
+        &mut out,
+        f,
+        output,
+        DisambiguateOptions::default(),
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+#[rstest]
+#[case::sparv("sparv", &mut SparvOutput)]
+#[case::json("json", &mut JsonOutput)]
+#[case::conllu("conllu", &mut ConlluOutput::default())]
+#[case::vrt("vrt", &mut VrtOutput::default())]
+#[case::sbxml("sbxml", &mut SbXmlOutput::new())]
+fn test_tab_input_with_other_output(
+    tiny_wsd: SharedWSDApplication,
+    #[case] name: &str,
+    #[case] output: &mut dyn OutputFormat,
+) -> eyre::Result<()> {
+    let actual = disambiguate(
+        tiny_wsd,
+        "assets/testing/example1.in.txt",
+        &mut TabFormat::default(),
+        Some(output),
+    )?;
+    insta::assert_snapshot!(format!("tab_to_{}", name), actual);
+    Ok(())
+}
+
+#[test]
+fn test_tab_output_matches_tab_format() -> eyre::Result<()> {
+    let input = "assets/testing/example1.in.txt";
+    let expected = disambiguate(tiny_wsd(), input, &mut TabFormat::default(), None)?;
+    let actual = disambiguate(
+        tiny_wsd(),
+        input,
+        &mut TabFormat::default(),
+        Some(&mut TabOutput),
+    )?;
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[rstest]
+fn test_sbxml_input_with_tab_output(tiny_wsd: SharedWSDApplication) -> eyre::Result<()> {
+    let actual = disambiguate(
+        tiny_wsd,
+        "assets/testing/example1.sbxml.xml",
+        &mut SbXmlFormat::new(),
+        Some(&mut TabOutput),
+    )?;
+    insta::assert_snapshot!(actual);
+    Ok(())
+}
+
+#[rstest]
+fn test_json_output_can_be_read_back(tiny_wsd: SharedWSDApplication) -> eyre::Result<()> {
+    let json = disambiguate(
+        tiny_wsd,
+        "assets/testing/example1.in.txt",
+        &mut TabFormat::default(),
+        Some(&mut JsonOutput),
+    )?;
+    let mut reader = io::BufReader::new(json.as_bytes());
+    let text = JsonlFormat::new().read_sentences(&mut reader, 10)?;
+
+    assert_eq!(text.len(), 1);
+    assert_eq!(text[0][2].word(), "betänkande");
+    assert_eq!(text[0][2].possible_lemmas(), ["betänkande..nn"]);
+    assert_eq!(text[0][2].possible_senses(), ["betänkande..1"]);
+    Ok(())
+}
+
+#[rstest]
+#[case::conllu(&mut ConlluOutput::default(), &mut ConlluFormat::default())]
+#[case::vrt(&mut VrtOutput::default(), &mut VrtFormat::default())]
+#[case::sbxml(&mut SbXmlOutput::new(), &mut SbXmlFormat::new())]
+#[case::jsonl(&mut JsonOutput, &mut JsonlFormat::new())]
+fn test_markup_output_can_be_read_back(
+    tiny_wsd: SharedWSDApplication,
+    #[case] output: &mut dyn OutputFormat,
+    #[case] format: &mut dyn SourceFormat,
+) -> eyre::Result<()> {
+    let input = "assets/testing/example1.in.txt";
+    let written = disambiguate(tiny_wsd, input, &mut TabFormat::default(), Some(output))?;
+    let expected =
+        TabFormat::default().read_sentences(&mut io::BufReader::new(fs::File::open(input)?), 10)?;
+
+    let text = format.read_sentences(&mut io::BufReader::new(written.as_bytes()), 10)?;
+
+    // the order of senses may change, and CoNLL-U keeps only one lemma
+    let sorted = |values: &[String]| {
+        let mut values = values.to_vec();
+        values.sort();
+        values
+    };
+    assert_eq!(text.len(), expected.len());
+    for (actual, expected) in text.iter().flatten().zip(expected.iter().flatten()) {
+        assert_eq!(actual.word(), expected.word());
+        for lemma in actual.possible_lemmas() {
+            assert!(expected.possible_lemmas().contains(lemma), "{}", lemma);
+        }
+        assert_eq!(
+            sorted(actual.possible_senses()),
+            sorted(expected.possible_senses())
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use std::fs;
+
+use process_corpus::LemmaToken;
+use rstest::rstest;
+use saldo::SaldoLexicon;
+
+use wsd_application::{
+    JsonlFormat, PageRankMode, PageRankOptions, PageRankWSD, WSDApplication, WSDError,
+    make_wsd_application,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+const SALDO: &str = "assets/testing/saldo.xml";
+
+fn utskott_anslag() -> Vec<LemmaToken> {
+    [
+        "1\tutskott\t_\t_\tutskott..nn\tutskott..1|utskott..2|utskott..3",
+        "2\tanslag\t_\t_\tanslag..nn\tanslag..1|anslag..2|anslag..3",
+        "3\tnyord\t_\t_\tnyord..nn\tnyord..1",
+    ]
+    .map(LemmaToken::parse_line)
+    .to_vec()
+}
+
+fn argmax(probs: &[f32]) -> usize {
+    probs
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(i, _)| i)
+        .unwrap()
+}
+
+#[test]
+fn test_graph_leaves_out_prim() -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new(SALDO)?;
+    let wsd = PageRankWSD::new(PageRankOptions::default(), &saldo);
+
+    assert_eq!(wsd.num_nodes(), saldo.len() - 1);
+    Ok(())
+}
+
+#[rstest]
+fn test_page_rank_prefers_connected_senses(
+    #[values(PageRankMode::Word, PageRankMode::Sentence)] mode: PageRankMode,
+) -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new(SALDO)?;
+    let wsd = PageRankWSD::new(
+        PageRankOptions {
+            mode,
+            ..PageRankOptions::default()
+        },
+        &saldo,
+    );
+    let lts = utskott_anslag();
+
+    let expected: Vec<_> = (0..lts.len()).map(|i| wsd.disambiguate(&lts, i)).collect();
+    let (_, disamb) = wsd.disambiguate_sentence(lts);
+
+    assert_eq!(disamb, expected);
+    let utskott = disamb[0].as_ref().expect("utskott is ambiguous");
+    let anslag = disamb[1].as_ref().expect("anslag is ambiguous");
+    // utskott..2 is linked to anslag..3, and utskott..3 only to PRIM..1
+    assert_eq!(argmax(utskott), 1, "{:?}", utskott);
+    if mode == PageRankMode::Word {
+        // unlinked senses are only ranked when seeded with themselves
+        assert_eq!(utskott[2], 0.0);
+        assert_eq!(anslag[1], 0.0);
+    }
+    for probs in [utskott, anslag] {
+        assert!((probs.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+    }
+    assert_eq!(disamb[2], None);
+    Ok(())
+}
+
+#[test]
+fn test_page_rank_without_context_senses() -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new(SALDO)?;
+    let wsd = PageRankWSD::new(
+        PageRankOptions {
+            context_width: Some(1),
+            ..PageRankOptions::default()
+        },
+        &saldo,
+    );
+    let mut lts = utskott_anslag();
+    lts.swap(1, 2);
+
+    // only nyord is in the window of utskott, and it is not linked to anything
+    assert_eq!(wsd.disambiguate(&lts, 0), None);
+    Ok(())
+}
+
+#[rstest]
+#[case::word("-pageRankMode=word")]
+#[case::sentence("-pageRankMode=sentence")]
+fn test_page_rank_wsd_jsonl(#[case] flag: &str) -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new(SALDO)?;
+    let wsd = make_wsd_application(Some(&saldo), "PageRankWSD", &[flag.to_string()])?;
+    let input = fs::read_to_string("assets/testing/example1.jsonl")?;
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd,
+        &mut input.as_bytes(),
+        &mut out,
+        &mut JsonlFormat::new(),
+        DisambiguateOptions::default(),
+    )?;
+
+    insta::assert_snapshot!(String::from_utf8(out)?);
+    Ok(())
+}
+
+#[test]
+fn test_page_rank_wsd_requires_saldo() {
+    let err = make_wsd_application(None, "PageRankWSD", &[]).err();
+    insta::assert_debug_snapshot!(err);
+}
+
+#[rstest]
+#[case::damping("-damping=1")]
+#[case::iterations("-iterations=many")]
+#[case::secondary_weight("-secondaryWeight=-1")]
+#[case::mode("-pageRankMode=graph")]
+fn test_page_rank_bad_flags(#[case] flag: &str) -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new(SALDO)?;
+    let result = make_wsd_application(Some(&saldo), "PageRankWSD", &[flag.to_string()]);
+    assert!(matches!(result, Err(WSDError::UsageError { .. })));
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use std::{fs, io};
+
+use process_corpus::LemmaToken;
+use rstest::rstest;
+
+use wsd_application::{
+    OutputFormat, SbXmlFormat, SharedWSDApplication, SourceFormat,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+use crate::common::tiny_wsd;
+
+const EXAMPLE: &str = "assets/testing/example1.sbxml.xml";
+
+fn read_all(
+    format: &mut SbXmlFormat,
+    reader: &mut dyn io::BufRead,
+    batch_size: usize,
+) -> eyre::Result<Vec<Vec<LemmaToken>>> {
+    let mut text = Vec::new();
+    loop {
+        let batch = format.read_sentences(reader, batch_size)?;
+        if batch.is_empty() {
+            return Ok(text);
+        }
+        text.extend(batch);
+    }
+}
+
+#[test]
+fn test_read_sbxml() -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let text = read_all(&mut SbXmlFormat::new(), &mut reader, 2)?;
+
+    let actual: Vec<Vec<String>> = text
+        .iter()
+        .map(|lts| lts.iter().map(LemmaToken::to_string).collect())
+        .collect();
+    insta::assert_debug_snapshot!(actual);
+    Ok(())
+}
+
+#[rstest]
+#[case::batch_1_small_buffer(1, 7)]
+#[case::batch_2(2, 8192)]
+#[case::batch_100(100, 8192)]
+fn test_sbxml_round_trip_without_scores(
+    #[case] batch_size: usize,
+    #[case] capacity: usize,
+) -> eyre::Result<()> {
+    let expected = fs::read_to_string(EXAMPLE)?;
+    let mut reader = io::BufReader::with_capacity(capacity, expected.as_bytes());
+    let mut format = SbXmlFormat::new();
+    let mut out = Vec::new();
+    for lts in read_all(&mut format, &mut reader, batch_size)? {
+        let scores = vec![None; lts.len()];
+        format.write_sentence(&mut out, &(lts, scores))?;
+    }
+    format.finish(&mut out)?;
+
+    assert_eq!(String::from_utf8(out)?, expected);
+    Ok(())
+}
+
+#[rstest]
+fn test_disambiguate_sbxml_tiny_models(
+    #[from(tiny_wsd)] wsd: SharedWSDApplication,
+) -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd,
+        &mut reader,
+        &mut out,
+        &mut SbXmlFormat::new(),
+        DisambiguateOptions::default(),
+    )?;
+
+    insta::assert_snapshot!(String::from_utf8(out)?);
+    Ok(())
+}
+
+#[test]
+fn test_read_sbxml_reports_malformed_xml() {
+    let mut reader = io::BufReader::new(
+        "<corpus>\n<sentence>\n<token lex=\"|a..nn.1|>a</token>\n</sentence>\n".as_bytes(),
+    );
+    let err = SbXmlFormat::new()
+        .read_sentences(&mut reader, 10)
+        .expect_err("unterminated attribute");
+    insta::assert_snapshot!(err.to_string());
+}
+
+#[test]
+fn test_read_sbxml_reports_truncated_sentence() {
+    let mut reader = io::BufReader::new(
+        "<corpus>\n<sentence>\n<token lex=\"|a..nn.1|\">a</token>\n<token>b</token>".as_bytes(),
+    );
+    let err = SbXmlFormat::new()
+        .read_sentences(&mut reader, 10)
+        .expect_err("unterminated sentence");
+    insta::assert_snapshot!(err.to_string());
+}
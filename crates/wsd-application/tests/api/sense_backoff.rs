@@ -0,0 +1,179 @@
+use std::fs;
+
+use hashbrown::HashMap;
+use ndarray::{Array1, array};
+use process_corpus::LemmaToken;
+use rstest::rstest;
+use saldo::SaldoLexicon;
+
+use wsd_application::{
+    JsonlFormat, SenseBackoff, VectorWSD, VectorWSDOptions, WSDApplication, WSDError,
+    make_wsd_application,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+use crate::common::tiny_argv;
+
+const SALDO: &str = "assets/testing/saldo.xml";
+
+/// Vectors for `anslag..1`, `utskott..2` and `regering..1`, but not for
+/// `anslag..3`, whose descriptors are `anslag..1` and `utskott..2`, nor for
+/// `ingå..2`, whose descriptors are `regering..1` and `anslag..1`.
+fn sense_vectors() -> HashMap<String, Array1<f32>> {
+    HashMap::from([
+        ("anslag..1".to_string(), array![1.0, 0.0]),
+        ("utskott..2".to_string(), array![0.0, 1.0]),
+        ("regering..1".to_string(), array![0.0, 2.0]),
+    ])
+}
+
+fn graph_wsd(saldo: &SaldoLexicon, backoff: Option<SenseBackoff>) -> VectorWSD {
+    let ctx_vectors: HashMap<String, Array1<f32>> =
+        HashMap::from([("pengar..nn".to_string(), array![0.0, 4.0])]);
+    let wsd = VectorWSD::new(
+        VectorWSDOptions {
+            context_width: 2,
+            ..VectorWSDOptions::default()
+        },
+        Box::new(sense_vectors()),
+        Box::new(ctx_vectors),
+    );
+    match backoff {
+        Some(backoff) => wsd.with_sense_backoff(saldo, backoff),
+        None => wsd,
+    }
+}
+
+fn sentence(senses: &str) -> Vec<LemmaToken> {
+    [
+        format!("1\tx\t_\t_\tx..nn\t{}", senses),
+        "2\tpengar\t_\t_\tpengar..nn\t_".to_string(),
+    ]
+    .iter()
+    .map(|line| LemmaToken::parse_line(line))
+    .collect()
+}
+
+#[test]
+fn test_backoff_neighbours() -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new(SALDO)?;
+    let sense_vectors = sense_vectors();
+
+    let neighbours = SenseBackoff::default().neighbours(&saldo, &sense_vectors);
+
+    let mut sorted: Vec<_> = neighbours.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    insta::assert_debug_snapshot!(sorted);
+    // PRIM..1 is never a neighbour, and senses with vectors are not approximated
+    assert!(!neighbours.contains_key("utskott..3"));
+    assert!(!neighbours.contains_key("anslag..1"));
+    for (sense, weights) in &neighbours {
+        let total: f32 = weights.iter().map(|(_, w)| w).sum();
+        assert!((total - 1.0).abs() < 1e-6, "{}: {:?}", sense, weights);
+    }
+    Ok(())
+}
+
+#[rstest]
+#[case::primary_only(SenseBackoff { primary: 1.0, secondary: 0.0, inverse: 0.0 }, vec![("anslag..1", 1.0)])]
+#[case::secondary_only(SenseBackoff { primary: 0.0, secondary: 1.0, inverse: 0.0 }, vec![("utskott..2", 1.0)])]
+#[case::both(SenseBackoff { primary: 3.0, secondary: 1.0, inverse: 0.0 }, vec![("anslag..1", 0.75), ("utskott..2", 0.25)])]
+fn test_backoff_weights(
+    #[case] backoff: SenseBackoff,
+    #[case] expected: Vec<(&str, f32)>,
+) -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new(SALDO)?;
+
+    let neighbours = backoff.neighbours(&saldo, &sense_vectors());
+
+    let expected: Vec<(String, f32)> = expected
+        .into_iter()
+        .map(|(id, w)| (id.to_string(), w))
+        .collect();
+    assert_eq!(neighbours.get("anslag..3"), Some(&expected));
+    Ok(())
+}
+
+#[test]
+fn test_backoff_makes_new_senses_possible() -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new(SALDO)?;
+    let without = graph_wsd(&saldo, None);
+    let with = graph_wsd(&saldo, Some(SenseBackoff::default()));
+    let lts = sentence("anslag..1|anslag..3");
+
+    // without a vector, anslag..3 can never be chosen
+    assert_eq!(without.disambiguate(&lts, 0), Some(vec![1.0, 0.0]));
+    // its approximation leans towards utskott..2, like the context
+    let probs = with
+        .disambiguate(&lts, 0)
+        .expect("both senses have vectors");
+    assert!(probs[1] > probs[0], "{:?}", probs);
+    let (_, disamb) = with.disambiguate_sentence(lts);
+    assert_eq!(disamb[0], Some(probs));
+    assert!(with.is_approximated("anslag..3"));
+    assert!(!with.is_approximated("anslag..1"));
+    assert!(!without.is_approximated("anslag..3"));
+    Ok(())
+}
+
+#[test]
+fn test_backoff_for_senses_without_any_vector() -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new(SALDO)?;
+    let lts = sentence("ingå..1|ingå..2");
+
+    assert_eq!(graph_wsd(&saldo, None).disambiguate(&lts, 0), None);
+    // ingå..1 only has PRIM..1 as descriptor
+    assert_eq!(
+        graph_wsd(&saldo, Some(SenseBackoff::default())).disambiguate(&lts, 0),
+        Some(vec![0.0, 1.0])
+    );
+    Ok(())
+}
+
+#[rstest]
+#[case::defaults("true", SenseBackoff::default())]
+#[case::weights("1,0,0.25", SenseBackoff { primary: 1.0, secondary: 0.0, inverse: 0.25 })]
+fn test_parse_sense_backoff(#[case] s: &str, #[case] expected: SenseBackoff) {
+    assert_eq!(s.parse::<SenseBackoff>().ok(), Some(expected));
+}
+
+#[rstest]
+#[case::too_few("1,0.5")]
+#[case::not_a_number("1,half,0.5")]
+#[case::negative("1,-0.5,0.5")]
+#[case::all_zero("0,0,0")]
+fn test_parse_sense_backoff_fails(#[case] s: &str) {
+    assert!(s.parse::<SenseBackoff>().is_err());
+}
+
+#[test]
+fn test_sense_backoff_jsonl() -> eyre::Result<()> {
+    let saldo = SaldoLexicon::new(SALDO)?;
+    let disambiguate = |flags: &[&str]| -> eyre::Result<String> {
+        let wsd = make_wsd_application(Some(&saldo), "VectorWSD", &tiny_argv(flags))?;
+        let input = fs::read_to_string("assets/testing/example1.jsonl")?;
+        let mut out = Vec::new();
+        disambiguate_sentences(
+            wsd,
+            &mut input.as_bytes(),
+            &mut out,
+            &mut JsonlFormat::new(),
+            DisambiguateOptions::default(),
+        )?;
+        Ok(String::from_utf8(out)?)
+    };
+
+    let out = disambiguate(&["-senseBackoff=true"])?;
+
+    // anslag..3 and ingå..2 have no vectors in the tiny model
+    assert_ne!(out, disambiguate(&[])?);
+    assert_eq!(disambiguate(&["-senseBackoff=false"])?, disambiguate(&[])?);
+    insta::assert_snapshot!(out);
+    Ok(())
+}
+
+#[test]
+fn test_sense_backoff_requires_saldo() {
+    let result = make_wsd_application(None, "VectorWSD", &tiny_argv(&["-senseBackoff=true"]));
+    assert!(matches!(result, Err(WSDError::UsageError { .. })));
+}
@@ -0,0 +1,95 @@
+use std::{
+    fs, io,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use process_corpus::LemmaToken;
+use wsd_application::{SourceFormat, TabFormat, WSDApplication, sense_ratios};
+
+use crate::common::tiny_wsd;
+
+const EXAMPLE: &str = "assets/testing/example1.in.txt";
+
+#[test]
+fn test_sense_ratios_tiny_models() -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let ratios = sense_ratios(
+        tiny_wsd(),
+        &mut reader,
+        &mut TabFormat::default(),
+        &["anslag..nn", "utskott", "saknas..nn"],
+        10,
+        usize::MAX,
+    )?;
+
+    insta::assert_snapshot!(ratios.to_string());
+    Ok(())
+}
+
+#[test]
+fn test_sense_ratios_match_full_disambiguation() -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let ratios = sense_ratios(
+        tiny_wsd(),
+        &mut reader,
+        &mut TabFormat::default(),
+        &["anslag..nn"],
+        1,
+        usize::MAX,
+    )?;
+
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let text = TabFormat::default().read_sentences(&mut reader, usize::MAX)?;
+    let mut expected = 0.0;
+    let mut occurrences = 0;
+    for (lts, scores) in tiny_wsd().disambiguate_text(text) {
+        for (lt, scores) in lts.iter().zip(scores) {
+            if lt.possible_lemmas().iter().any(|l| l == "anslag..nn") {
+                occurrences += 1;
+                expected += f64::from(scores.expect("anslag is ambiguous")[0]);
+            }
+        }
+    }
+
+    let (target, anslag) = &ratios.lemmas[0];
+    assert_eq!(target, "anslag..nn");
+    assert_eq!(anslag.occurrences, occurrences);
+    assert_eq!(anslag.scored, occurrences);
+    assert!((anslag.expected["anslag..1"] - expected).abs() < 1e-6);
+    assert_eq!(anslag.argmax.values().sum::<usize>(), occurrences);
+    Ok(())
+}
+
+/// Counts the tokens it is asked to score.
+#[derive(Default)]
+struct CountsCalls(AtomicUsize);
+
+impl WSDApplication for CountsCalls {
+    fn disambiguate(&self, lts: &[LemmaToken], i: usize) -> Option<Vec<f32>> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        let n = lts[i].possible_senses().len();
+        Some(vec![1.0 / n as f32; n])
+    }
+}
+
+#[test]
+fn test_sense_ratios_only_score_targets() -> eyre::Result<()> {
+    let wsd = Arc::new(CountsCalls::default());
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let ratios = sense_ratios(
+        wsd.clone(),
+        &mut reader,
+        &mut TabFormat::default(),
+        &["anslag..nn"],
+        10,
+        usize::MAX,
+    )?;
+
+    let (_, anslag) = &ratios.lemmas[0];
+    assert!(anslag.occurrences > 0);
+    assert_eq!(wsd.0.load(Ordering::Relaxed), anslag.occurrences);
+    Ok(())
+}
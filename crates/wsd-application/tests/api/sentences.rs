@@ -0,0 +1,56 @@
+use std::{fs, io};
+
+use process_corpus::LemmaToken;
+use rstest::rstest;
+
+use wsd_application::{JsonlFormat, SbXmlFormat, Sentences, SourceFormat};
+
+use crate::common::make_format;
+
+#[rstest]
+#[case::tab("tab", "assets/testing/example1.in.txt")]
+#[case::sbxml("sbxml", "assets/testing/example1.sbxml.xml")]
+#[case::conllu("conllu", "assets/testing/example1.conllu")]
+#[case::vrt("vrt", "assets/testing/example1.vrt")]
+#[case::jsonl("jsonl", "assets/testing/example1.jsonl")]
+fn test_sentences_match_batches(#[case] name: &str, #[case] path: &str) -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(path)?);
+    let streamed: Vec<Vec<LemmaToken>> = make_format(name)
+        .sentences(&mut reader)
+        .collect::<Result<_, _>>()?;
+
+    let mut reader = io::BufReader::new(fs::File::open(path)?);
+    let batched = make_format(name).read_sentences(&mut reader, usize::MAX)?;
+
+    assert!(!streamed.is_empty());
+    assert_eq!(streamed, batched);
+    Ok(())
+}
+
+#[test]
+fn test_sentences_with_adapters() -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open("assets/testing/example1.sbxml.xml")?);
+    let mut format = SbXmlFormat::new();
+    let words: Vec<Vec<String>> = format
+        .sentences(&mut reader)
+        .skip(1)
+        .filter(|lts| lts.as_ref().map_or(true, |lts| lts.len() < 5))
+        .take(1)
+        .map(|lts| Ok(lts?.iter().map(|lt| lt.word().to_string()).collect()))
+        .collect::<Result<_, process_corpus::CorpusError>>()?;
+
+    assert_eq!(words, [["AU1", "&", "utskott"]]);
+    Ok(())
+}
+
+#[test]
+fn test_sentences_stop_after_error() {
+    let input = "[{\"word\":\"a\"}]\nnot json\n[{\"word\":\"b\"}]\n";
+    let mut reader = io::BufReader::new(input.as_bytes());
+    let mut format = JsonlFormat::new();
+    let results: Vec<_> = Sentences::new(&mut format, &mut reader).collect();
+
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+}
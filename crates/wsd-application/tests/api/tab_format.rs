@@ -0,0 +1,178 @@
+use std::{fs, io};
+
+use process_corpus::{LemmaToken, ParseMode};
+use rstest::rstest;
+
+use wsd_application::{
+    SharedWSDApplication, SourceFormat, TabColumn, TabColumns, TabFormat,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+use crate::common::tiny_wsd;
+
+const EXAMPLE: &str = "assets/testing/example1.in.txt";
+
+fn read_all(
+    format: &mut TabFormat,
+    reader: &mut dyn io::BufRead,
+    batch_size: usize,
+) -> eyre::Result<Vec<Vec<LemmaToken>>> {
+    let mut text = Vec::new();
+    loop {
+        let batch = format.read_sentences(reader, batch_size)?;
+        if batch.is_empty() {
+            return Ok(text);
+        }
+        text.extend(batch);
+    }
+}
+
+type Fields<'a> = (&'a str, &'a [String], &'a [String], &'a [String]);
+
+/// Everything but the positions, which are numbered when not read from a column.
+fn fields(text: &[Vec<LemmaToken>]) -> Vec<Vec<Fields<'_>>> {
+    text.iter()
+        .map(|lts| {
+            lts.iter()
+                .map(|lt| {
+                    (
+                        lt.word(),
+                        lt.possible_lemgrams(),
+                        lt.possible_lemmas(),
+                        lt.possible_senses(),
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The example with named columns in another order and `;` between values.
+fn example_with_header() -> eyre::Result<String> {
+    let mut out = String::from("lemgram\tform\tsaldo\tlemma\n");
+    for line in fs::read_to_string(EXAMPLE)?.lines() {
+        let cols: Vec<&str> = line.split('\t').collect();
+        let cols = [cols[3], cols[1], cols[5], cols[4]].map(|col| col.replace('|', ";"));
+        out.push_str(&cols.join("\t"));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn columns_with_header() -> TabColumns {
+    TabColumns {
+        separator: ';',
+        .."word:form,lemgrams:lemgram,lemmas:lemma,senses:saldo"
+            .parse()
+            .unwrap()
+    }
+}
+
+#[rstest]
+#[case::sparv("sparv", TabColumns::sparv())]
+#[case::indices("word:0,lemmas:2", TabColumns {
+    word: TabColumn::Index(0),
+    pos: None,
+    lemgrams: None,
+    lemmas: Some(TabColumn::Index(2)),
+    senses: None,
+    separator: '|',
+    header: false,
+})]
+#[case::names("word:form,msd:msd,senses:5", TabColumns {
+    word: TabColumn::Name("form".into()),
+    pos: Some(TabColumn::Name("msd".into())),
+    lemgrams: None,
+    lemmas: None,
+    senses: Some(TabColumn::Index(5)),
+    separator: '|',
+    header: true,
+})]
+fn test_parse_tab_columns(#[case] spec: &str, #[case] expected: TabColumns) {
+    assert_eq!(spec.parse::<TabColumns>(), Ok(expected));
+}
+
+#[rstest]
+#[case::no_word("lemmas:2")]
+#[case::unknown_field("word:0,gloss:1")]
+#[case::no_column("word")]
+fn test_parse_tab_columns_fails(#[case] spec: &str) {
+    assert!(spec.parse::<TabColumns>().is_err());
+}
+
+#[rstest]
+#[case::batch_1(1)]
+#[case::batch_100(100)]
+fn test_read_named_columns_like_sparv(#[case] batch_size: usize) -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let expected = read_all(&mut TabFormat::default(), &mut reader, batch_size)?;
+
+    let input = example_with_header()?;
+    let mut reader = io::BufReader::new(input.as_bytes());
+    let mut format = TabFormat::with_columns(ParseMode::Strict, columns_with_header());
+    let text = read_all(&mut format, &mut reader, batch_size)?;
+
+    assert_eq!(fields(&text), fields(&expected));
+    Ok(())
+}
+
+#[test]
+fn test_read_pos_column() -> eyre::Result<()> {
+    let input = "anslag\tNN.NEU.SIN.IND.NOM\tanslag\tanslag..1|anslag..2\n\
+                 ger\tVB.PRS.AKT\tge..vb\t_\n";
+    let mut reader = io::BufReader::new(input.as_bytes());
+    let columns = "word:0,pos:1,lemmas:2,senses:3"
+        .parse()
+        .map_err(eyre::Report::msg)?;
+    let text = read_all(
+        &mut TabFormat::with_columns(ParseMode::Strict, columns),
+        &mut reader,
+        10,
+    )?;
+
+    assert_eq!(
+        text,
+        [[
+            LemmaToken::new(
+                "1",
+                "anslag",
+                vec![],
+                vec!["anslag..nn".into()],
+                vec!["anslag..1".into(), "anslag..2".into()],
+            ),
+            LemmaToken::new("2", "ger", vec![], vec!["ge..vb".into()], vec![]),
+        ]]
+    );
+    Ok(())
+}
+
+#[rstest]
+fn test_disambiguate_named_columns_tiny_models(
+    #[from(tiny_wsd)] wsd: SharedWSDApplication,
+) -> eyre::Result<()> {
+    let input = example_with_header()?;
+    let mut reader = io::BufReader::new(input.as_bytes());
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd,
+        &mut reader,
+        &mut out,
+        &mut TabFormat::with_columns(ParseMode::Strict, columns_with_header()),
+        DisambiguateOptions::default(),
+    )?;
+
+    insta::assert_snapshot!(String::from_utf8(out)?);
+    Ok(())
+}
+
+#[rstest]
+#[case::unknown_name("form\tlemma\nanslag\tanslag..nn\n", "word:word,lemmas:lemma")]
+#[case::missing_columns("anslag\n", "word:0,lemmas:1")]
+fn test_read_tab_columns_error(#[case] input: &str, #[case] spec: &str) -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(input.as_bytes());
+    let err = TabFormat::with_columns(ParseMode::Strict, spec.parse().map_err(eyre::Report::msg)?)
+        .read_sentences(&mut reader, 10)
+        .expect_err("bad columns");
+    insta::assert_snapshot!(err.to_string());
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::fs;
+
+use rstest::rstest;
+
+use wsd_application::{
+    SharedWSDApplication,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+use crate::common::{make_format, tiny_wsd};
+
+fn disambiguate(
+    wsd: &SharedWSDApplication,
+    name: &str,
+    input: &str,
+    opts: DisambiguateOptions,
+) -> eyre::Result<String> {
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd.clone(),
+        &mut input.as_bytes(),
+        &mut out,
+        &mut make_format(name),
+        opts,
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+#[test]
+fn test_wsd_application_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedWSDApplication>();
+}
+
+#[rstest]
+#[case::tab("tab", "assets/testing/example1.in.txt")]
+#[case::sbxml("sbxml", "assets/testing/example1.sbxml.xml")]
+#[case::conllu("conllu", "assets/testing/example1.conllu")]
+#[case::vrt("vrt", "assets/testing/example1.vrt")]
+#[case::jsonl("jsonl", "assets/testing/example1.jsonl")]
+fn test_threads_give_identical_output(
+    #[case] name: &str,
+    #[case] path: &str,
+    #[values(1, 2, 100)] batch_size: usize,
+    #[values(0, 3)] threads: usize,
+) -> eyre::Result<()> {
+    let input = fs::read_to_string(path)?;
+    let wsd = tiny_wsd();
+    let opts = DisambiguateOptions {
+        batch_size,
+        ..DisambiguateOptions::default()
+    };
+    let expected = disambiguate(&wsd, name, &input, opts)?;
+    let actual = disambiguate(&wsd, name, &input, DisambiguateOptions { threads, ..opts })?;
+
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[rstest]
+#[case::threads_1(1)]
+#[case::threads_4(4)]
+fn test_threads_respect_max_sen(#[case] threads: usize) -> eyre::Result<()> {
+    let input = fs::read_to_string("assets/testing/example1.jsonl")?;
+    let wsd = tiny_wsd();
+    let opts = DisambiguateOptions {
+        batch_size: 1,
+        max_sen: 2,
+        threads,
+        ..DisambiguateOptions::default()
+    };
+    let out = disambiguate(&wsd, "jsonl", &input, opts)?;
+
+    assert_eq!(input.lines().count(), 3);
+    assert_eq!(out.lines().count(), 3);
+    Ok(())
+}
@@ -1,12 +1,18 @@
-use std::{env, fs, io};
+use std::{fs, io};
 
+use hashbrown::HashMap;
+use ndarray::{Array1, array};
+use process_corpus::LemmaToken;
 use rstest::{fixture, rstest};
 
 use wsd_application::{
-    SharedWSDApplication, TabFormat, make_wsd_application,
+    SharedWSDApplication, SourceFormat, TabFormat, VectorWSD, VectorWSDOptions, WSDApplication,
+    make_wsd_application,
     wsd_application::{DisambiguateOptions, disambiguate_sentences},
 };
 
+use crate::common::{tiny_argv, tiny_wsd_with};
+
 #[fixture]
 fn sense_model() -> &'static str {
     "assets/sparv-wsd/models/scouse/ALL_512_128_w10_A2_140403_ctx1.bin"
@@ -40,7 +46,7 @@ fn test_vector_wsd(vector_wsd: SharedWSDApplication) -> eyre::Result<()> {
         vector_wsd,
         &mut reader,
         &mut out,
-        &TabFormat::default(),
+        &mut TabFormat::default(),
         DisambiguateOptions::default(),
     )?;
 
@@ -49,3 +55,133 @@ fn test_vector_wsd(vector_wsd: SharedWSDApplication) -> eyre::Result<()> {
     insta::assert_debug_snapshot!(actual);
     Ok(())
 }
+
+#[rstest]
+#[case::map("map")]
+#[case::matrix("matrix")]
+#[case::mmap("mmap")]
+fn test_vector_wsd_tiny_models(#[case] vector_store: &str) -> eyre::Result<()> {
+    let wsd = tiny_wsd_with(&[&format!("-vectorStore={}", vector_store)]);
+    let mut reader = io::BufReader::new(fs::File::open("assets/testing/example1.in.txt")?);
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd,
+        &mut reader,
+        &mut out,
+        &mut TabFormat::default(),
+        DisambiguateOptions::default(),
+    )?;
+
+    let actual = String::from_utf8(out)?;
+    let actual: Vec<&str> = actual.split('\n').collect();
+    insta::assert_debug_snapshot!("vector_wsd_tiny_models", actual);
+    Ok(())
+}
+
+#[rstest]
+#[case::map("map")]
+#[case::matrix("matrix")]
+#[case::mmap("mmap")]
+fn test_sentence_scores_match_token_scores(#[case] vector_store: &str) -> eyre::Result<()> {
+    let argv = tiny_argv(&[&format!("-vectorStore={}", vector_store)]);
+    let wsd = VectorWSD::from_args(None, &argv)?;
+    let mut reader = io::BufReader::new(fs::File::open("assets/testing/example1.in.txt")?);
+    let text = TabFormat::default().read_sentences(&mut reader, usize::MAX)?;
+
+    for lts in text {
+        let expected: Vec<Option<Vec<f32>>> =
+            (0..lts.len()).map(|i| wsd.disambiguate(&lts, i)).collect();
+        let (_, actual) = wsd.disambiguate_sentence(lts);
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(&expected) {
+            match (actual, expected) {
+                (Some(actual), Some(expected)) => {
+                    assert_eq!(actual.len(), expected.len());
+                    for (a, e) in actual.iter().zip(expected) {
+                        assert!((a - e).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+                    }
+                }
+                _ => assert_eq!(actual, expected),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_vector_wsd_with_injected_embeddings() {
+    let sense_vectors: HashMap<String, Array1<f32>> = HashMap::from([
+        ("bank..1".to_string(), array![1.0, 0.0]),
+        ("bank..2".to_string(), array![0.0, 1.0]),
+    ]);
+    let ctx_vectors: HashMap<String, Array1<f32>> =
+        HashMap::from([("pengar..nn".to_string(), array![0.0, 4.0])]);
+    let wsd = VectorWSD::new(
+        VectorWSDOptions {
+            decay: false,
+            s1prior: 0.0,
+            context_width: 2,
+            ..VectorWSDOptions::default()
+        },
+        Box::new(sense_vectors),
+        Box::new(ctx_vectors),
+    );
+    let lts = [
+        "1\tbank\t_\t_\tbank..nn\tbank..1|bank..2",
+        "2\tpengar\t_\t_\tpengar..nn\t_",
+    ]
+    .map(LemmaToken::parse_line);
+
+    let probs = wsd.disambiguate(&lts, 0).expect("bank is ambiguous");
+
+    assert!(probs[1] > probs[0], "probs={:?}", probs);
+    assert!((probs.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+    assert_eq!(wsd.disambiguate(&lts, 1), None);
+    assert_eq!(
+        wsd.disambiguate_sentence(lts.to_vec()).1,
+        [Some(probs), None]
+    );
+}
+
+#[test]
+fn test_vector_wsd_neighbours() -> eyre::Result<()> {
+    let argv = &[
+        "-svFile=assets/testing/models/sense.bin".to_string(),
+        "-cvFile=assets/testing/models/context.bin".to_string(),
+    ];
+    let wsd = VectorWSD::from_args(None, argv)?;
+    let mut out = Vec::new();
+
+    wsd.write_neighbours(
+        &["anvisa..vb", "anslag..3", "riksdag..1"],
+        3,
+        false,
+        &mut out,
+    )?;
+
+    let actual = String::from_utf8(out)?;
+    insta::assert_snapshot!(actual);
+    Ok(())
+}
+
+#[test]
+fn test_neighbours_lists_senses_by_number() -> eyre::Result<()> {
+    let sense_vectors: HashMap<String, Array1<f32>> = ["bank..10", "bank..2", "bank..1"]
+        .into_iter()
+        .map(|id| (id.to_string(), array![1.0, 0.0]))
+        .collect();
+    let ctx_vectors: HashMap<String, Array1<f32>> = HashMap::new();
+    let wsd = VectorWSD::new(
+        VectorWSDOptions::default(),
+        Box::new(sense_vectors),
+        Box::new(ctx_vectors),
+    );
+    let mut out = Vec::new();
+
+    wsd.write_neighbours(&["bank..nn"], 1, false, &mut out)?;
+
+    let out = String::from_utf8(out)?;
+    let queries: Vec<&str> = out.lines().filter(|line| line.starts_with('#')).collect();
+    assert_eq!(queries, ["# bank..1", "# bank..2", "# bank..10"]);
+    Ok(())
+}
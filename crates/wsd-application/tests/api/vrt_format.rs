@@ -0,0 +1,121 @@
+use std::{fs, io};
+
+use process_corpus::LemmaToken;
+use rstest::rstest;
+
+use wsd_application::{
+    OutputFormat, SharedWSDApplication, SourceFormat, VrtAttributes, VrtFormat,
+    wsd_application::{DisambiguateOptions, disambiguate_sentences},
+};
+
+use crate::common::tiny_wsd;
+
+const EXAMPLE: &str = "assets/testing/example1.vrt";
+
+fn read_all(
+    format: &mut VrtFormat,
+    reader: &mut dyn io::BufRead,
+    batch_size: usize,
+) -> eyre::Result<Vec<Vec<LemmaToken>>> {
+    let mut text = Vec::new();
+    loop {
+        let batch = format.read_sentences(reader, batch_size)?;
+        if batch.is_empty() {
+            return Ok(text);
+        }
+        text.extend(batch);
+    }
+}
+
+fn to_strings(text: &[Vec<LemmaToken>]) -> Vec<Vec<String>> {
+    text.iter()
+        .map(|lts| lts.iter().map(LemmaToken::to_string).collect())
+        .collect()
+}
+
+#[test]
+fn test_read_vrt() -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let text = read_all(&mut VrtFormat::default(), &mut reader, 1)?;
+
+    insta::assert_debug_snapshot!(to_strings(&text));
+    Ok(())
+}
+
+#[test]
+fn test_read_vrt_without_header() -> eyre::Result<()> {
+    let input = "<sentence>\nanslag\tutskott..nn\tanslag..1|anslag..2\n</sentence>\n";
+    let mut reader = io::BufReader::new(input.as_bytes());
+    let mut format = VrtFormat::new(VrtAttributes {
+        positional: ["token", "lem", "saldo"].map(String::from).to_vec(),
+        word: "token".into(),
+        lemma: "lem".into(),
+        sense: "saldo".into(),
+        ..VrtAttributes::default()
+    });
+    let text = read_all(&mut format, &mut reader, 10)?;
+
+    assert_eq!(
+        to_strings(&text),
+        [["1\tanslag\t_\t_\tutskott..nn\tanslag..1|anslag..2"]]
+    );
+    Ok(())
+}
+
+#[rstest]
+#[case::batch_1(1)]
+#[case::batch_100(100)]
+fn test_vrt_adds_empty_scores_attribute(#[case] batch_size: usize) -> eyre::Result<()> {
+    let input = fs::read_to_string(EXAMPLE)?;
+    let mut reader = io::BufReader::new(input.as_bytes());
+    let mut format = VrtFormat::default();
+    let mut out = Vec::new();
+    for lts in read_all(&mut format, &mut reader, batch_size)? {
+        let scores = vec![None; lts.len()];
+        format.write_sentence(&mut out, &(lts, scores))?;
+    }
+    format.finish(&mut out)?;
+
+    let expected: Vec<String> = input
+        .lines()
+        .map(|line| match line {
+            "<!-- #vrt positional-attributes: word pos lemma lex sense -->" => {
+                "<!-- #vrt positional-attributes: word pos lemma lex sense wsd -->".to_string()
+            }
+            line if line.starts_with('<') => line.to_string(),
+            line => format!("{}\t|", line),
+        })
+        .collect();
+    assert_eq!(
+        String::from_utf8(out)?.lines().collect::<Vec<_>>(),
+        expected
+    );
+    Ok(())
+}
+
+#[rstest]
+fn test_disambiguate_vrt_tiny_models(
+    #[from(tiny_wsd)] wsd: SharedWSDApplication,
+) -> eyre::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(EXAMPLE)?);
+    let mut out = Vec::new();
+    disambiguate_sentences(
+        wsd,
+        &mut reader,
+        &mut out,
+        &mut VrtFormat::default(),
+        DisambiguateOptions::default(),
+    )?;
+
+    insta::assert_snapshot!(String::from_utf8(out)?);
+    Ok(())
+}
+
+#[test]
+fn test_read_vrt_reports_missing_columns() {
+    let mut reader = io::BufReader::new("<sentence>\nanslag\tNN\n</sentence>\n".as_bytes());
+    let err = VrtFormat::default()
+        .read_sentences(&mut reader, 10)
+        .expect_err("too few columns");
+    insta::assert_snapshot!(err.to_string());
+}